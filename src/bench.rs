@@ -0,0 +1,263 @@
+//! Benchmark matrix over processors, trig backends, objects, hologram
+//! resolutions and rayon thread counts.
+//!
+//! Every combination is timed over a few runs of `CghProcessor::field`, and
+//! reported with its throughput in point·pixels per second, its parallel
+//! efficiency against the fewest threads measured, and the phase error of its
+//! field against the naive processor with exact trigonometry. The results can
+//! be written as CSV or JSON, to follow them across commits.
+
+use ndarray::{Array2, Zip};
+use num::Complex;
+use rayon::ThreadPoolBuilder;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::time::Instant;
+
+use crate::cgh::naive::CghProcessorNaiveParallel;
+use crate::cgh::{CghConfig, CghProcessor};
+use crate::error::{Error, Result};
+use crate::job::{ProcessorKind, Settings, Trig};
+use crate::lut::TriFuncNaive;
+use crate::object::Object3d;
+use crate::point::Point;
+use crate::random::SplitMix64;
+
+#[derive(Debug)]
+pub struct Matrix {
+    pub processors: Vec<ProcessorKind>,
+    /// Settings the processors are built with.
+    pub settings: Settings,
+    pub trigs: Vec<Trig>,
+    /// Objects with the names they are reported under.
+    pub objects: Vec<(String, Object3d)>,
+    pub resolutions: Vec<(u32, u32)>,
+    /// Sizes of the rayon thread pools, at least one and all positive.
+    /// Serial processors only run with the first one.
+    pub threads: Vec<usize>,
+    /// Timed runs per combination, after one untimed warm-up run.
+    pub repeat: usize,
+    /// Geometry shared by every run; the resolution and the lateral offset
+    /// are set from `resolutions`, centering the objects.
+    pub config: CghConfig,
+    /// Whether to compute the naive reference fields for `Measurement::phase_rms`.
+    pub accuracy: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Measurement {
+    pub processor: String,
+    /// `-` for processors that do not use the trig backend, which are only
+    /// run once whatever `Matrix::trigs`.
+    pub trig: String,
+    pub object: String,
+    pub points: usize,
+    pub width: u32,
+    pub height: u32,
+    pub threads: usize,
+    /// Mean time of the timed runs.
+    pub seconds: f64,
+    pub min_seconds: f64,
+    /// Points times pixels over the mean time.
+    pub throughput: f64,
+    /// Speedup over the run with the fewest threads of the same combination,
+    /// divided by the increase in threads: 1 for perfect scaling.
+    pub efficiency: f64,
+    /// See `phase_rms`, against the naive processor with `TriFuncNaive`.
+    pub phase_rms: Option<f64>,
+}
+
+impl Matrix {
+    /// Runs every combination, calling `progress` as each one is measured.
+    /// Fails before running anything if there are no thread counts or a
+    /// trig backend cannot be built.
+    pub fn run<F: FnMut(&Measurement)>(&self, mut progress: F) -> Result<Vec<Measurement>> {
+        if self.threads.is_empty() || self.threads.contains(&0) {
+            return Err(Error::InvalidBench(
+                "thread counts must be given and positive".to_string(),
+            ));
+        }
+        let tri_funcs = self
+            .trigs
+            .iter()
+            .map(|trig| trig.build())
+            .collect::<Result<Vec<_>>>()?;
+        let pools: Vec<_> = self
+            .threads
+            .iter()
+            .map(|&threads| {
+                let pool = ThreadPoolBuilder::new()
+                    .num_threads(threads)
+                    .build()
+                    .expect("failed to start a thread pool");
+                (threads, pool)
+            })
+            .collect();
+        let mut references = HashMap::new();
+        let mut measurements = Vec::new();
+
+        for &(width, height) in &self.resolutions {
+            let config = CghConfig {
+                image_width: width,
+                image_height: height,
+                offset: Point::new(
+                    width as f64 / 2.0,
+                    height as f64 / 2.0,
+                    self.config.offset.z,
+                ),
+                ..self.config
+            };
+
+            for (index, (name, object)) in self.objects.iter().enumerate() {
+                for &processor in &self.processors {
+                    // Processors that ignore the trig backend run once, with
+                    // the first one.
+                    let uses_tri_func = processor.uses_tri_func();
+                    let count = if uses_tri_func { self.trigs.len() } else { 1 };
+                    for (trig, tri_func) in self.trigs.iter().zip(&tri_funcs).take(count) {
+                        let trig = match uses_tri_func {
+                            true => trig.to_string(),
+                            false => "-".to_string(),
+                        };
+                        let pools = match processor.is_parallel() {
+                            true => &pools[..],
+                            false => &pools[..pools.len().min(1)],
+                        };
+
+                        let first = measurements.len();
+                        for (threads, pool) in pools {
+                            let (field, times) = pool.install(|| {
+                                let built = processor.build(&self.settings);
+                                let field = built.field(tri_func.as_ref(), &config, object);
+                                let times: Vec<_> = (0..self.repeat.max(1))
+                                    .map(|_| {
+                                        let start = Instant::now();
+                                        built.field(tri_func.as_ref(), &config, object);
+                                        start.elapsed().as_secs_f64()
+                                    })
+                                    .collect();
+                                (field, times)
+                            });
+
+                            let phase_rms = self.accuracy.then(|| {
+                                let reference = references.entry(index).or_insert_with(|| {
+                                    CghProcessorNaiveParallel::default().field(
+                                        &TriFuncNaive,
+                                        &config,
+                                        object,
+                                    )
+                                });
+                                phase_rms(&field, reference)
+                            });
+
+                            let seconds = times.iter().sum::<f64>() / times.len() as f64;
+                            let work = object.size() as f64 * width as f64 * height as f64;
+                            measurements.push(Measurement {
+                                processor: processor.to_string(),
+                                trig: trig.clone(),
+                                object: name.clone(),
+                                points: object.size(),
+                                width,
+                                height,
+                                threads: *threads,
+                                seconds,
+                                min_seconds: times.iter().copied().fold(f64::INFINITY, f64::min),
+                                throughput: work / seconds,
+                                efficiency: 1.0,
+                                phase_rms,
+                            });
+                        }
+
+                        let base = measurements[first..]
+                            .iter()
+                            .min_by_key(|m| m.threads)
+                            .map_or(0.0, |m| m.seconds * m.threads as f64);
+                        for measurement in &mut measurements[first..] {
+                            measurement.efficiency =
+                                base / (measurement.seconds * measurement.threads as f64);
+                            progress(measurement);
+                        }
+                    }
+                }
+            }
+
+            references.clear();
+        }
+
+        Ok(measurements)
+    }
+}
+
+/// `count` points spread uniformly over the cube `-1..=1`, reproducible for a
+/// given `seed`.
+pub fn point_cloud(count: usize, seed: u64) -> Object3d {
+    let mut random = SplitMix64::new(seed);
+    let mut coordinate = || 2.0 * random.next_f64() - 1.0;
+    let points = (0..count)
+        .map(|_| Point::new(coordinate(), coordinate(), coordinate()))
+        .collect();
+
+    Object3d::new(points)
+}
+
+/// RMS of the phase difference between `field` and `reference` in radians,
+/// weighted by the reference intensity, after removing the constant phase
+/// offset between them (which does not change the hologram).
+pub fn phase_rms(field: &Array2<Complex<f64>>, reference: &Array2<Complex<f64>>) -> f64 {
+    let correlation: Complex<f64> = Zip::from(field)
+        .and(reference)
+        .fold(Complex::default(), |sum, &f, &r| sum + f * r.conj());
+    let offset = Complex::from_polar(1.0, -correlation.arg());
+
+    let (error, weight) =
+        Zip::from(field)
+            .and(reference)
+            .fold((0.0, 0.0), |(error, weight), &f, &r| {
+                let difference = (f * offset * r.conj()).arg();
+                let w = r.norm_sqr();
+                (error + w * difference.powi(2), weight + w)
+            });
+
+    (error / weight).sqrt()
+}
+
+pub fn to_csv(measurements: &[Measurement]) -> String {
+    let mut csv = String::from(
+        "processor,trig,object,points,width,height,threads,seconds,min_seconds,throughput,efficiency,phase_rms\n",
+    );
+    for m in measurements {
+        let phase_rms = m.phase_rms.map(|e| e.to_string()).unwrap_or_default();
+        writeln!(
+            csv,
+            "{},{},{},{},{},{},{},{},{},{},{},{}",
+            m.processor,
+            m.trig,
+            csv_field(&m.object),
+            m.points,
+            m.width,
+            m.height,
+            m.threads,
+            m.seconds,
+            m.min_seconds,
+            m.throughput,
+            m.efficiency,
+            phase_rms
+        )
+        .unwrap();
+    }
+
+    csv
+}
+
+pub fn to_json(measurements: &[Measurement]) -> String {
+    serde_json::to_string_pretty(measurements).expect("measurements serialize to JSON")
+}
+
+/// Quotes a CSV field if it contains a separator, quote or line break.
+fn csv_field(value: &str) -> String {
+    match value.contains([',', '"', '\n']) {
+        true => format!("\"{}\"", value.replace('"', "\"\"")),
+        false => value.to_string(),
+    }
+}
@@ -1,8 +1,7 @@
 use holography::bmp;
-use std::io;
 
-fn main() -> io::Result<()> {
-    let mut image = bmp::Image::new(256, 256);
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut image = bmp::Image::new(256, 256)?;
 
     let distance_max = ((image.height().pow(2) + image.width().pow(2)) as f64).sqrt();
 
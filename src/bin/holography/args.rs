@@ -0,0 +1,101 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::Result;
+
+/// `--name value` (or `--name=value`) options of a subcommand. Each option is
+/// taken once by the subcommand, and `finish` rejects the ones left over.
+pub struct Args {
+    options: BTreeMap<String, String>,
+}
+
+impl Args {
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self> {
+        let mut options = BTreeMap::new();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            let Some(option) = arg.strip_prefix("--") else {
+                return Err(format!("unexpected argument `{}`", arg).into());
+            };
+            let (name, value) = match option.split_once('=') {
+                Some((name, value)) => (name.to_string(), value.to_string()),
+                None => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| format!("missing value for --{}", option))?;
+                    (option.to_string(), value)
+                }
+            };
+
+            if options.insert(name.clone(), value).is_some() {
+                return Err(format!("--{} given more than once", name).into());
+            }
+        }
+
+        Ok(Args { options })
+    }
+
+    pub fn get<T>(&mut self, name: &str) -> Result<Option<T>>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        self.options
+            .remove(name)
+            .map(|value| {
+                value
+                    .parse()
+                    .map_err(|error| format!("invalid value `{}` for --{}: {}", value, name, error))
+            })
+            .transpose()
+            .map_err(Into::into)
+    }
+
+    pub fn get_or<T>(&mut self, name: &str, default: T) -> Result<T>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        Ok(self.get(name)?.unwrap_or(default))
+    }
+
+    pub fn require<T>(&mut self, name: &str) -> Result<T>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        self.get(name)?
+            .ok_or_else(|| format!("missing --{}", name).into())
+    }
+
+    pub fn finish(self) -> Result<()> {
+        match self.options.keys().next() {
+            Some(name) => Err(format!("unknown option --{}", name).into()),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Comma-separated values, e.g. `--offset 960,540,125000`.
+pub struct List<T>(pub Vec<T>);
+
+impl<T: FromStr> FromStr for List<T>
+where
+    T::Err: Display,
+{
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        value
+            .split(',')
+            .map(|item| {
+                item.trim()
+                    .parse()
+                    .map_err(|error: T::Err| error.to_string())
+            })
+            .collect::<std::result::Result<_, _>>()
+            .map(List)
+    }
+}
@@ -0,0 +1,381 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use holography::bench::{point_cloud, to_csv, to_json, Matrix, Measurement};
+use holography::bmp::Image;
+use holography::cgh::encode_image;
+use holography::cgh::encoding::Kinoform;
+use holography::cgh::SampleDepth;
+use holography::ifta::{target_from_image, Algorithm, Ifta};
+use holography::job::{
+    Depth, EncoderKind, Hologram, Input, Job, Output, ProcessorKind, Transform, Trig,
+};
+use holography::object::{Object3d, ObjectFormat};
+use holography::point::Point;
+use holography::propagation::{Method, Propagator};
+use holography::raster::{ImageFormat, Raster};
+use holography::reconstruction::{intensity_image, phase_from_image, Reconstruction};
+
+use crate::args::{Args, List};
+use crate::options::{self, Resolution, Samples};
+use crate::Result;
+
+const DEFAULT_OBJECT: &str = "data/cube284.3d";
+
+fn create_parent(path: &Path) -> Result<()> {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => Ok(fs::create_dir_all(parent)?),
+        _ => Ok(()),
+    }
+}
+
+fn open_object(args: &mut Args) -> Result<(PathBuf, Object3d)> {
+    let path = args.get_or("input", PathBuf::from(DEFAULT_OBJECT))?;
+    let format = options::object_format(args, &path)?;
+    let object = Object3d::open_as(&path, format)?;
+
+    Ok((path, object))
+}
+
+/// Computes a hologram from `--job`, or from the options otherwise. Either
+/// way the resolved job is written next to the output, unless `--sidecar
+/// false` is given.
+pub fn generate(mut args: Args) -> Result<()> {
+    if let Some(path) = args.get::<PathBuf>("job")? {
+        args.finish()?;
+        let job = Job::open(path)?;
+        create_parent(&job.output.path)?;
+        return Ok(job.run()?);
+    }
+
+    let input = args.get_or("input", PathBuf::from(DEFAULT_OBJECT))?;
+    let depth = match args.get::<PathBuf>("depth")? {
+        Some(path) => Some(Depth {
+            path,
+            range: args.get_or("depth-range", 1250.0)?,
+        }),
+        None => None,
+    };
+    let input_format = match depth {
+        Some(_) => None,
+        None => Some(options::object_format(&mut args, &input)?),
+    };
+    let output = args.get_or("output", PathBuf::from("out/hologram.bmp"))?;
+    let output_format = options::image_format(&mut args, "output-format", &output)?;
+    let Samples(sample_depth) = args.get_or("sample-depth", Samples(SampleDepth::Eight))?;
+    let settings = options::settings(&mut args)?;
+    let settings = options::encoder_settings(&mut args, settings)?;
+    let job = Job {
+        processor: args.get_or("processor", ProcessorKind::default())?,
+        encoder: args.get_or("encoder", EncoderKind::default())?,
+        trig: options::trig(&mut args)?,
+        settings,
+        hologram: options::hologram(&mut args)?,
+        input: Input {
+            path: input,
+            format: input_format,
+            transform: Transform::default(),
+            sample_spacing: args.get("sample-spacing")?,
+            random_phase: args.get("random-phase")?,
+            depth,
+            color_by_position: args.get_or("color-by-position", false)?,
+        },
+        output: Output {
+            path: output,
+            format: Some(output_format),
+            sample_depth,
+            bits_per_pixel: args.get("bits-per-pixel")?,
+            sidecar: args.get_or("sidecar", true)?,
+        },
+    };
+    args.finish()?;
+
+    create_parent(&job.output.path)?;
+    Ok(job.run()?)
+}
+
+/// Writes one intensity image per depth, named after the output path with
+/// the depth in millimeters appended, with as many decimals as the depths
+/// need.
+pub fn reconstruct(mut args: Args) -> Result<()> {
+    let input: PathBuf = args.require("input")?;
+    let output = args.get_or("output", PathBuf::from("out/reconstruct.bmp"))?;
+    let format = options::image_format(&mut args, "output-format", &output)?;
+    let List(depths) = args.get_or("depths", List(vec![1.0]))?;
+    let method = match args.get_or("method", "asm".to_string())?.as_str() {
+        "asm" => Method::AngularSpectrum,
+        "fresnel" => Method::FresnelConvolution,
+        other => return Err(format!("unknown method `{}`, expected asm or fresnel", other).into()),
+    };
+    let hologram = Image::open(&input)?;
+    let pixel_pitch = match args.get("pixel-pitch")? {
+        Some(pixel_pitch) => pixel_pitch,
+        None => hologram.pixel_pitch().unwrap_or(8.0e-6),
+    };
+    let wavelength = args.get_or("wavelength", 520.0e-9)?;
+    args.finish()?;
+
+    let reconstruction = Reconstruction {
+        propagator: Propagator::new(pixel_pitch, wavelength),
+        method,
+    };
+    let phase = phase_from_image(&hologram);
+
+    let stem = output.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = output.extension().unwrap_or_default().to_string_lossy();
+    let decimals = millimeter_decimals(&depths);
+    create_parent(&output)?;
+    for depth in depths {
+        let intensity = reconstruction.intensity(&phase, depth);
+        let image = intensity_image(&intensity, pixel_pitch)?;
+        let name = format!("{}-{:.*}mm.{}", stem, decimals, depth * 1e3, extension);
+        let path = output.with_file_name(name);
+        Raster::from(&image).save(path, format)?;
+    }
+
+    Ok(())
+}
+
+/// The fewest decimals, up to nanometers, that write every depth (in meters)
+/// exactly in millimeters, so that distinct depths get distinct names.
+fn millimeter_decimals(depths: &[f64]) -> usize {
+    (0..6)
+        .find(|&decimals| {
+            let factor = 10f64.powi(decimals as i32);
+            depths.iter().all(|depth| {
+                let scaled = depth * 1e3 * factor;
+                (scaled - scaled.round()).abs() < 1e-6 * scaled.abs().max(1.0)
+            })
+        })
+        .unwrap_or(6)
+}
+
+pub fn convert(mut args: Args) -> Result<()> {
+    let input: PathBuf = args.require("input")?;
+    let input_format = options::image_format(&mut args, "input-format", &input)?;
+    let output: PathBuf = args.require("output")?;
+    let output_format = options::image_format(&mut args, "output-format", &output)?;
+    args.finish()?;
+
+    let raster = Raster::open(&input, input_format)?;
+    create_parent(&output)?;
+    raster.save(&output, output_format)?;
+
+    Ok(())
+}
+
+/// Times the object field computation, without encoding or output, over
+/// every combination of the listed processors, trig backends, objects,
+/// resolutions and thread counts.
+pub fn bench(mut args: Args) -> Result<()> {
+    let objects = match args.get::<List<usize>>("points")? {
+        Some(List(counts)) => counts
+            .into_iter()
+            .map(|count| (format!("cloud-{}", count), point_cloud(count, 0)))
+            .collect(),
+        None => {
+            let (path, object) = open_object(&mut args)?;
+            vec![(path.display().to_string(), object)]
+        }
+    };
+    let hologram = options::hologram(&mut args)?;
+    let List(resolutions) = args.get_or(
+        "resolutions",
+        List(vec![Resolution(hologram.width, hologram.height)]),
+    )?;
+    let List(processors) = args.get_or("processors", List(vec![ProcessorKind::default()]))?;
+    let settings = options::settings(&mut args)?;
+    let List(trigs) = args.get_or("trigs", List(vec![Trig::default()]))?;
+    let List(threads) = args.get_or("threads", List(options::thread_counts()))?;
+    let repeat = args.get_or("repeat", 3_usize)?;
+    let accuracy = args.get_or("accuracy", true)?;
+    let output: Option<PathBuf> = args.get("output")?;
+    args.finish()?;
+
+    hologram.validate()?;
+    settings.validate()?;
+    if hologram.wavelengths.is_some() {
+        return Err("bench does not support --wavelengths".into());
+    }
+    let write: Option<fn(&[Measurement]) -> String> = match &output {
+        Some(path) => match path.extension().and_then(|e| e.to_str()) {
+            Some("csv") => Some(to_csv),
+            Some("json") => Some(to_json),
+            _ => return Err("--output must be a .csv or .json file".into()),
+        },
+        None => None,
+    };
+
+    let matrix = Matrix {
+        processors,
+        settings,
+        trigs,
+        objects,
+        resolutions: resolutions.iter().map(|r| (r.0, r.1)).collect(),
+        threads,
+        repeat,
+        config: hologram.config(),
+        accuracy,
+    };
+    let measurements = matrix.run(|m| {
+        let phase_rms = match m.phase_rms {
+            Some(error) => format!("{:.2e} rad", error),
+            None => "-".to_string(),
+        };
+        println!(
+            "{:<20} {:<10} {:>7} points {:>5}x{:<5} {:>3} threads  {:>8.3}s  {:.3e} point·pixels/s  efficiency {:.2}  phase RMS {}",
+            m.processor,
+            m.trig,
+            m.points,
+            m.width,
+            m.height,
+            m.threads,
+            m.seconds,
+            m.throughput,
+            m.efficiency,
+            phase_rms
+        );
+    })?;
+
+    if let (Some(path), Some(write)) = (output, write) {
+        create_parent(&path)?;
+        fs::write(path, write(&measurements))?;
+    }
+
+    Ok(())
+}
+
+/// Computes a phase-only Fourier hologram of a target image with an
+/// iterative Fourier transform algorithm, printing the error of each
+/// iteration.
+pub fn ifta(mut args: Args) -> Result<()> {
+    let input: PathBuf = args.require("input")?;
+    let output = args.get_or("output", PathBuf::from("out/ifta.bmp"))?;
+    let format = options::image_format(&mut args, "output-format", &output)?;
+    let defaults = Hologram::default();
+    let width: u32 = args.get_or("width", defaults.width)?;
+    let height: u32 = args.get_or("height", defaults.height)?;
+    let pixel_pitch = args.get_or("pixel-pitch", defaults.pixel_pitch)?;
+    let mixing = args.get_or("mixing", 0.5)?;
+    let algorithm = match args.get_or("algorithm", "wgs".to_string())?.as_str() {
+        "gs" => Algorithm::GerchbergSaxton,
+        "wgs" => Algorithm::WeightedGerchbergSaxton,
+        "mraf" => Algorithm::Mraf { mixing },
+        other => {
+            return Err(format!("unknown algorithm `{}`, expected gs, wgs or mraf", other).into())
+        }
+    };
+    let iterations = args.get_or("iterations", 50)?;
+    let seed = args.get_or("seed", 0)?;
+    args.finish()?;
+
+    if !(0.0..=1.0).contains(&mixing) {
+        return Err("--mixing must be within 0..=1".into());
+    }
+    let target = target_from_image(&Image::open(&input)?);
+    let (target_height, target_width) = target.dim();
+    if target_width > width as usize || target_height > height as usize {
+        return Err(format!("the target is larger than {}x{}", width, height).into());
+    }
+
+    let ifta = Ifta {
+        algorithm,
+        iterations,
+        seed,
+    };
+    let retrieval = ifta.run_with(
+        &target,
+        width as usize,
+        height as usize,
+        |iteration, error| println!("iteration {:3}: error {:.4}", iteration + 1, error),
+    );
+
+    let image = encode_image(&Kinoform, &retrieval.field, pixel_pitch)?;
+    create_parent(&output)?;
+    Raster::from(&image).save(&output, format)?;
+
+    Ok(())
+}
+
+/// Prints the largest and RMS error of trig backends against `f64::sin_cos`,
+/// by default of every LUT size and mode.
+pub fn lut_error(mut args: Args) -> Result<()> {
+    let List(trigs) = args.get_or("trigs", List(Trig::luts()))?;
+    let samples = args.get_or("samples", 100_000_usize)?;
+    args.finish()?;
+
+    if samples == 0 {
+        return Err("--samples must be positive".into());
+    }
+
+    println!("{:<20} {:>10} {:>10}", "trig", "max", "rms");
+    for trig in trigs {
+        let error = trig.error(samples)?;
+        println!(
+            "{:<20} {:>10.3e} {:>10.3e}",
+            trig.to_string(),
+            error.max,
+            error.rms
+        );
+    }
+
+    Ok(())
+}
+
+/// Describes an object or image, told apart by extension.
+pub fn info(mut args: Args) -> Result<()> {
+    let input: PathBuf = args.require("input")?;
+    args.finish()?;
+
+    if let Some(format) = ObjectFormat::from_path(&input) {
+        return object_info(&Object3d::open_as(&input, format)?);
+    }
+
+    match ImageFormat::from_path(&input) {
+        Some(ImageFormat::Bmp) => {
+            let image = Image::open(&input)?;
+            println!("image: {}x{} pixels", image.width(), image.height());
+            println!("bits per pixel: {}", image.bits_per_pixel());
+            if let Some(pixel_pitch) = image.pixel_pitch() {
+                println!("pixel pitch: {:.3e} m", pixel_pitch);
+            }
+        }
+        Some(format) => {
+            let raster = Raster::open(&input, format)?;
+            println!("image: {}x{} pixels", raster.width(), raster.height());
+            println!("channels: {}", raster.pixels().channels());
+        }
+        None => return Err(format!("cannot tell the format of {}", input.display()).into()),
+    }
+
+    Ok(())
+}
+
+fn object_info(object: &Object3d) -> Result<()> {
+    println!("points: {}", object.size());
+    if !object.faces().is_empty() {
+        println!("faces: {}", object.faces().len());
+    }
+    println!("colors: {}", object.colors().is_some());
+
+    let (min, max) = object.points().iter().fold(
+        (
+            Point::new(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+            Point::new(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+        ),
+        |(min, max), p| {
+            (
+                Point::new(min.x.min(p.x), min.y.min(p.y), min.z.min(p.z)),
+                Point::new(max.x.max(p.x), max.y.max(p.y), max.z.max(p.z)),
+            )
+        },
+    );
+    if object.size() > 0 {
+        println!(
+            "bounds: x {}..{}, y {}..{}, z {}..{}",
+            min.x, max.x, min.y, max.y, min.z, max.z
+        );
+    }
+
+    Ok(())
+}
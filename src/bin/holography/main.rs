@@ -0,0 +1,165 @@
+//! Command-line front end: computes holograms of objects, reconstructs them
+//! and converts between image formats.
+//!
+//! Run `holography help` for the list of subcommands and options.
+
+mod args;
+mod commands;
+mod options;
+
+use std::env;
+use std::process::ExitCode;
+
+use self::args::Args;
+
+pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+const USAGE: &str = "\
+usage: holography <command> [--option value]...
+
+commands:
+  generate     compute a hologram of an object
+  reconstruct  reconstruct a phase hologram at one or more depths
+  convert      convert an image to another format
+  bench        time the field computation of a processor
+  ifta         compute a Fourier hologram of a 2D target image
+  lut-error    measure the error of the trig lookup tables
+  info         describe an object or image file
+
+object options (generate, bench):
+  --input PATH            object file [data/cube284.3d]
+  --input-format FORMAT   3d, ply, obj or stl [from the extension]
+  --width N --height N    hologram resolution [1920x1080]
+  --pixel-pitch M         pixel pitch in meters [8e-6]
+  --wavelength M          wavelength in meters [520e-9]
+  --offset X,Y,Z          object position in pixels [centered, 1m away]
+  --scale S               object scale [40]
+  --reference WAVE        reference wave: plane:ANGLE_X,ANGLE_Y (radians) or
+                          spherical:X,Y,Z (pixels) [none]
+  --reference-amplitude A relative to the object wave [1]
+  --interference MODE     intensity or bipolar [intensity]
+
+processor options (generate, bench):
+  --occlusion BOOL        hide occluded points (naive and fresnel) [false]
+  --light X,Y,Z           polygon: direction towards the light [0,0,-1]
+  --ambient A             polygon: share of ambient light [0.1]
+  --cull-back-faces BOOL  polygon: skip faces turned away [true]
+  --layers N              layer: number of depth slices [32]
+  --layer-method NAME     layer: asm or fresnel [asm]
+  --layer-random-phase N  layer: seed of random phases per pixel [none]
+  --wrp-distance D        wrp: distance of the WRP in pixels [256]
+
+generate options:
+  --processor NAME        naive, fresnel, recurrence, simd (each also as
+                          NAME-parallel), wrp, polygon or layer
+                          [recurrence-parallel]
+  --trig NAME             naive or lut [naive]
+  --lut-size N            256, 1024, 4096, 16384 or 65536 [256]
+  --lut-mode MODE         truncate, round, linear or quadratic [truncate]
+  --job PATH              TOML or JSON job file, instead of the other options
+                          (see jobs/ for examples)
+  --sample-spacing D      cover the faces of meshes with points D apart
+  --random-phase SEED     random initial phases of the points [none]
+  --depth PATH            read --input as the image of an RGB-D pair with
+                          this depth map (BMP or PGM); use --scale 1
+  --depth-range R         depth of the farthest level in pixels [1250]
+  --color-by-position BOOL
+                          color the points red along x, green along y and
+                          blue along z [false]
+  --wavelengths R,G,B     compute a color hologram at these wavelengths,
+                          e.g. 638e-9,520e-9,450e-9, instead of --wavelength;
+                          needs a --sample-depth of 8 or 16
+  --output PATH           output image [out/hologram.bmp]
+  --output-format FORMAT  bmp, png, pnm or tiff [from the extension]
+  --encoder NAME          kinoform, amplitude, binary, double-phase,
+                          error-diffusion or complex-error-diffusion [kinoform]
+  --amplitude-bias B      amplitude: reference amplitude relative to the
+                          object [1]
+  --diffusion-levels N    error diffusion: number of levels [2, or 16 for
+                          complex-error-diffusion]
+  --diffusion-kernel NAME error diffusion: floyd-steinberg or
+                          jarvis-judice-ninke [floyd-steinberg]
+  --sample-depth DEPTH    8, 16, float (raw phase in radians, without the
+                          encoder) or float-level (encoded levels) [8]
+  --bits-per-pixel N      1, 4 or 8 for grayscale BMP output with 8-bit
+                          samples [8]
+  --sidecar BOOL          write the resolved job next to the output as
+                          NAME.job.toml [true]
+
+reconstruct options:
+  --input PATH            8-bit phase hologram (BMP)
+  --output PATH           output image, suffixed with each depth
+                          [out/reconstruct.bmp]
+  --output-format FORMAT  bmp, png, pnm or tiff [from the extension]
+  --depths D,...          distances in meters [1]
+  --method NAME           asm or fresnel [asm]
+  --pixel-pitch M         [from the hologram, else 8e-6]
+  --wavelength M          [520e-9]
+
+convert options:
+  --input PATH --output PATH
+  --input-format FORMAT   bmp or pnm [from the extension]
+  --output-format FORMAT  bmp, png, pnm or tiff [from the extension]
+
+bench options (lists are comma-separated):
+  --processors NAME,...   as for --processor [recurrence-parallel]
+  --trigs NAME,...        naive, lut, lut:SIZE or lut:SIZE:MODE [naive];
+                          simd and layer ignore them and run once
+  --points N,...          random point clouds of N points in -1..=1,
+                          instead of --input
+  --resolutions WxH,...   [--width x --height]; objects are recentered
+  --threads N,...         rayon thread counts, serial processors only run
+                          with the first [powers of two up to the CPU count]
+  --repeat N              timed runs per combination [3]
+  --accuracy BOOL         measure the phase error against the naive
+                          processor [true]
+  --output PATH           also write the results as .csv or .json
+
+ifta options:
+  --input PATH            8-bit target image (BMP), centered on the image plane
+  --output PATH           output image [out/ifta.bmp]
+  --output-format FORMAT  bmp, png, pnm or tiff [from the extension]
+  --width N --height N    hologram resolution [1920x1080]
+  --pixel-pitch M         [8e-6]
+  --algorithm NAME        gs, wgs or mraf [wgs]
+  --mixing M              mraf: share of the target in 0..=1 [0.5]
+  --iterations N          [50]
+  --seed N                seed of the random initial phase [0]
+
+lut-error options:
+  --trigs NAME,...        as for bench [every LUT size and mode]
+  --samples N             phases measured over a cycle [100000]
+
+info options:
+  --input PATH            object or image file";
+
+fn main() -> ExitCode {
+    let mut arguments = env::args().skip(1);
+    let Some(command) = arguments.next() else {
+        eprintln!("{}", USAGE);
+        return ExitCode::FAILURE;
+    };
+
+    let result = Args::parse(arguments).and_then(|args| match command.as_str() {
+        "generate" => commands::generate(args),
+        "reconstruct" => commands::reconstruct(args),
+        "convert" => commands::convert(args),
+        "bench" => commands::bench(args),
+        "ifta" => commands::ifta(args),
+        "lut-error" => commands::lut_error(args),
+        "info" => commands::info(args),
+        "help" | "-h" | "--help" => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        command => Err(format!("unknown command `{}`\n\n{}", command, USAGE).into()),
+    });
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
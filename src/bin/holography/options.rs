@@ -0,0 +1,202 @@
+use std::path::Path;
+use std::str::FromStr;
+use std::thread;
+
+use holography::cgh::occlusion::Occlusion;
+use holography::cgh::reference::{Interference, Reference, ReferenceWave};
+use holography::cgh::SampleDepth;
+use holography::job::{Hologram, Settings, Trig, LUT_SIZES};
+use holography::object::ObjectFormat;
+use holography::point::Point;
+use holography::raster::ImageFormat;
+
+use crate::args::{Args, List};
+use crate::Result;
+
+/// `SampleDepth` by name, for `--sample-depth`.
+pub struct Samples(pub SampleDepth);
+
+impl FromStr for Samples {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value {
+            "8" => Ok(Samples(SampleDepth::Eight)),
+            "16" => Ok(Samples(SampleDepth::Sixteen)),
+            "float" => Ok(Samples(SampleDepth::Float)),
+            "float-level" => Ok(Samples(SampleDepth::FloatLevel)),
+            _ => Err("expected 8, 16, float or float-level".to_string()),
+        }
+    }
+}
+
+/// `WIDTH`x`HEIGHT`, e.g. `--resolutions 512x512,1920x1080`.
+pub struct Resolution(pub u32, pub u32);
+
+impl FromStr for Resolution {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        let error = || format!("expected WIDTHxHEIGHT, got `{}`", value);
+        let (width, height) = value.split_once('x').ok_or_else(error)?;
+        match (width.parse(), height.parse()) {
+            (Ok(width), Ok(height)) if width > 0 && height > 0 => Ok(Resolution(width, height)),
+            _ => Err(error()),
+        }
+    }
+}
+
+/// `plane:ANGLE_X,ANGLE_Y` in radians or `spherical:X,Y,Z` in pixels, for
+/// `--reference`.
+pub struct Wave(pub ReferenceWave);
+
+impl FromStr for Wave {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        let error = || "expected plane:ANGLE_X,ANGLE_Y or spherical:X,Y,Z".to_string();
+        let (kind, values) = value.split_once(':').ok_or_else(error)?;
+        let List(values) = values.parse::<List<f64>>()?;
+        match (kind, &values[..]) {
+            ("plane", &[angle_x, angle_y]) => Ok(Wave(ReferenceWave::Plane { angle_x, angle_y })),
+            ("spherical", &[x, y, z]) => Ok(Wave(ReferenceWave::Spherical {
+                source: Point::new(x, y, z),
+            })),
+            _ => Err(error()),
+        }
+    }
+}
+
+/// Three comma-separated values, e.g. `--light -1,-1,-2`.
+fn point(args: &mut Args, name: &str) -> Result<Option<Point>> {
+    match args.get::<List<f64>>(name)? {
+        Some(List(values)) => match values[..] {
+            [x, y, z] => Ok(Some(Point::new(x, y, z))),
+            _ => Err(format!("--{} takes three values: x,y,z", name).into()),
+        },
+        None => Ok(None),
+    }
+}
+
+/// Powers of two up to the available parallelism, and that number itself.
+pub fn thread_counts() -> Vec<usize> {
+    let available = thread::available_parallelism().map_or(1, usize::from);
+    let mut counts: Vec<_> = (0..)
+        .map(|power| 1 << power)
+        .take_while(|&count| count < available)
+        .collect();
+    counts.push(available);
+    counts
+}
+
+/// Hologram geometry from `--width`, `--height`, `--pixel-pitch`,
+/// `--wavelength`, `--wavelengths`, `--offset` and `--scale`, and the
+/// reference wave from `--reference`, `--reference-amplitude` and
+/// `--interference`. The object is centered 1m in front of the hologram by
+/// default.
+pub fn hologram(args: &mut Args) -> Result<Hologram> {
+    let defaults = Hologram::default();
+    let offset = point(args, "offset")?;
+    let wavelengths = match args.get::<List<f64>>("wavelengths")? {
+        Some(List(values)) => match values[..] {
+            [red, green, blue] => Some([red, green, blue]),
+            _ => return Err("--wavelengths takes three values: red,green,blue".into()),
+        },
+        None => None,
+    };
+    let amplitude = args.get_or("reference-amplitude", 1.0)?;
+    let mode = args.get_or("interference", Interference::Intensity)?;
+    let reference = args.get::<Wave>("reference")?.map(|Wave(wave)| Reference {
+        wave,
+        amplitude,
+        mode,
+    });
+
+    Ok(Hologram {
+        width: args.get_or("width", defaults.width)?,
+        height: args.get_or("height", defaults.height)?,
+        pixel_pitch: args.get_or("pixel-pitch", defaults.pixel_pitch)?,
+        wavelength: args.get_or("wavelength", defaults.wavelength)?,
+        scale: args.get_or("scale", defaults.scale)?,
+        offset,
+        reference,
+        wavelengths,
+    })
+}
+
+/// Processor settings from `--occlusion`, `--light`, `--ambient`,
+/// `--cull-back-faces`, `--layers`, `--layer-method`, `--layer-random-phase`
+/// and `--wrp-distance`.
+pub fn settings(args: &mut Args) -> Result<Settings> {
+    let defaults = Settings::default();
+
+    Ok(Settings {
+        occlusion: args.get_or("occlusion", false)?.then(Occlusion::default),
+        light: point(args, "light")?.unwrap_or(defaults.light),
+        ambient: args.get_or("ambient", defaults.ambient)?,
+        cull_back_faces: args.get_or("cull-back-faces", defaults.cull_back_faces)?,
+        layers: args.get_or("layers", defaults.layers)?,
+        layer_method: args.get_or("layer-method", defaults.layer_method)?,
+        layer_random_phase: args.get("layer-random-phase")?,
+        wrp_distance: args.get_or("wrp-distance", defaults.wrp_distance)?,
+        ..defaults
+    })
+}
+
+/// `settings` with the encoder settings from `--amplitude-bias`,
+/// `--diffusion-levels` and `--diffusion-kernel`.
+pub fn encoder_settings(args: &mut Args, settings: Settings) -> Result<Settings> {
+    Ok(Settings {
+        amplitude_bias: args.get_or("amplitude-bias", settings.amplitude_bias)?,
+        diffusion_levels: args.get("diffusion-levels")?,
+        diffusion_kernel: args.get_or("diffusion-kernel", settings.diffusion_kernel)?,
+        ..settings
+    })
+}
+
+/// Trig backend from `--trig`, `--lut-size` and `--lut-mode`.
+pub fn trig(args: &mut Args) -> Result<Trig> {
+    let defaults = Trig::default();
+    let trig = Trig {
+        kind: args.get_or("trig", defaults.kind)?,
+        lut_size: args.get_or("lut-size", defaults.lut_size)?,
+        lut_mode: args.get_or("lut-mode", defaults.lut_mode)?,
+    };
+    if !LUT_SIZES.contains(&trig.lut_size) {
+        return Err(format!("unsupported --lut-size, expected one of: {:?}", LUT_SIZES).into());
+    }
+
+    Ok(trig)
+}
+
+/// Object format from `--input-format`, or else from the extension of `path`.
+pub fn object_format(args: &mut Args, path: &Path) -> Result<ObjectFormat> {
+    let format = match args.get::<String>("input-format")? {
+        Some(name) => match name.as_str() {
+            "3d" => Some(ObjectFormat::Points),
+            "ply" => Some(ObjectFormat::Ply),
+            "obj" => Some(ObjectFormat::Obj),
+            "stl" => Some(ObjectFormat::Stl),
+            _ => return Err(format!("unknown object format `{}`", name).into()),
+        },
+        None => ObjectFormat::from_path(path),
+    };
+
+    format.ok_or_else(|| format!("cannot tell the format of {}", path.display()).into())
+}
+
+/// Image format from `--{option}`, or else from the extension of `path`.
+pub fn image_format(args: &mut Args, option: &str, path: &Path) -> Result<ImageFormat> {
+    let format = match args.get::<String>(option)? {
+        Some(name) => match name.as_str() {
+            "bmp" => Some(ImageFormat::Bmp),
+            "png" => Some(ImageFormat::Png),
+            "pnm" | "pgm" | "ppm" => Some(ImageFormat::Pnm),
+            "tiff" => Some(ImageFormat::Tiff),
+            _ => return Err(format!("unknown image format `{}`", name).into()),
+        },
+        None => ImageFormat::from_path(path),
+    };
+
+    format.ok_or_else(|| format!("cannot tell the format of {}", path.display()).into())
+}
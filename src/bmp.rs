@@ -1,7 +1,39 @@
+use crate::error::{Error, Result};
 use std::fs::File;
-use std::io::{self, Write};
+use std::io::{self, BufReader, Read, Write};
 use std::path::Path;
 
+fn invalid_data(message: &str) -> Error {
+    Error::InvalidImage(message.to_string())
+}
+
+fn read_u16<R: Read>(source: &mut R) -> io::Result<u16> {
+    let mut buffer = [0; 2];
+    source.read_exact(&mut buffer)?;
+    Ok(u16::from_le_bytes(buffer))
+}
+
+fn read_u32<R: Read>(source: &mut R) -> io::Result<u32> {
+    let mut buffer = [0; 4];
+    source.read_exact(&mut buffer)?;
+    Ok(u32::from_le_bytes(buffer))
+}
+
+fn read_i32<R: Read>(source: &mut R) -> io::Result<i32> {
+    let mut buffer = [0; 4];
+    source.read_exact(&mut buffer)?;
+    Ok(i32::from_le_bytes(buffer))
+}
+
+fn skip<R: Read>(source: &mut R, count: u64) -> io::Result<()> {
+    let skipped = io::copy(&mut source.take(count), &mut io::sink())?;
+    if skipped != count {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+
+    Ok(())
+}
+
 #[derive(Debug)]
 struct BmpFileHeader {
     file_type: [u8; 2],
@@ -33,6 +65,22 @@ impl BmpFileHeader {
 
         Ok(())
     }
+
+    fn from_reader<R: Read>(source: &mut R) -> Result<Self> {
+        let mut file_type = [0; 2];
+        source.read_exact(&mut file_type)?;
+        if &file_type != b"BM" {
+            return Err(invalid_data("not a BMP file"));
+        }
+
+        Ok(BmpFileHeader {
+            file_type,
+            file_size: read_u32(source)?,
+            reserved1: read_u16(source)?,
+            reserved2: read_u16(source)?,
+            data_offset: read_u32(source)?,
+        })
+    }
 }
 
 #[derive(Debug)]
@@ -53,22 +101,31 @@ struct BmpInfoHeader {
 impl BmpInfoHeader {
     const HEADER_SIZE: u32 = 40;
 
-    fn new(width: i32, height: i32) -> Self {
+    const COMPRESSION_RGB: u32 = 0;
+    const COMPRESSION_BITFIELDS: u32 = 3;
+
+    fn new(width: i32, height: i32, image_size: u32) -> Self {
         BmpInfoHeader {
             header_size: Self::HEADER_SIZE, // fixed
             width,
             height,
-            planes: 1,             // fixed
-            bits_per_pixel: 8,     // fixed
-            compression: 0,        // fixed
-            image_size: 0,         // fixed
-            x_pixels_per_meter: 0, // fixed
-            y_pixels_per_meter: 0, // fixed
-            colors_used: 0,        // fixed
-            colors_important: 0,   // fixed
+            planes: 1,         // fixed
+            bits_per_pixel: 8, // fixed
+            compression: 0,    // fixed
+            image_size,
+            x_pixels_per_meter: 0,
+            y_pixels_per_meter: 0,
+            colors_used: 0,      // fixed
+            colors_important: 0, // fixed
         }
     }
 
+    /// Number of bytes in one stored row, padded to a 4-byte boundary.
+    #[inline]
+    fn stride(width: u32, bits_per_pixel: u16) -> usize {
+        (width as usize * bits_per_pixel as usize).div_ceil(32) * 4
+    }
+
     fn to_writer<W: Write>(&self, distination: &mut W) -> io::Result<()> {
         distination.write_all(&self.header_size.to_le_bytes())?;
         distination.write_all(&self.width.to_le_bytes())?;
@@ -84,6 +141,45 @@ impl BmpInfoHeader {
 
         Ok(())
     }
+
+    /// Reads a `BITMAPINFOHEADER` or one of its larger successors (V4, V5).
+    /// Fields beyond the basic 40 bytes are skipped, except for the channel
+    /// masks, which are returned when present.
+    fn from_reader<R: Read>(source: &mut R) -> Result<(Self, Option<[u32; 4]>)> {
+        let header_size = read_u32(source)?;
+        if header_size < Self::HEADER_SIZE {
+            return Err(Error::UnsupportedFormat("BMP core header".to_string()));
+        }
+
+        let info = BmpInfoHeader {
+            header_size,
+            width: read_i32(source)?,
+            height: read_i32(source)?,
+            planes: read_u16(source)?,
+            bits_per_pixel: read_u16(source)?,
+            compression: read_u32(source)?,
+            image_size: read_u32(source)?,
+            x_pixels_per_meter: read_i32(source)?,
+            y_pixels_per_meter: read_i32(source)?,
+            colors_used: read_u32(source)?,
+            colors_important: read_u32(source)?,
+        };
+
+        let mut masks = None;
+        let mut remaining = (header_size - Self::HEADER_SIZE) as u64;
+        if remaining >= 16 {
+            masks = Some([
+                read_u32(source)?,
+                read_u32(source)?,
+                read_u32(source)?,
+                read_u32(source)?,
+            ]);
+            remaining -= 16;
+        }
+        skip(source, remaining)?;
+
+        Ok((info, masks))
+    }
 }
 
 #[derive(Debug)]
@@ -105,13 +201,73 @@ impl BmpColorPallet {
     }
 
     fn to_writer<W: Write>(&self, distination: &mut W) -> io::Result<()> {
-        distination.write_all(&self.r.to_le_bytes())?;
-        distination.write_all(&self.g.to_le_bytes())?;
         distination.write_all(&self.b.to_le_bytes())?;
+        distination.write_all(&self.g.to_le_bytes())?;
+        distination.write_all(&self.r.to_le_bytes())?;
         distination.write_all(&self.reserved.to_le_bytes())?;
 
         Ok(())
     }
+
+    fn from_reader<R: Read>(source: &mut R) -> io::Result<Self> {
+        let mut buffer = [0; 4];
+        source.read_exact(&mut buffer)?;
+        let [b, g, r, reserved] = buffer;
+
+        Ok(BmpColorPallet { r, g, b, reserved })
+    }
+
+    fn gray(&self) -> u8 {
+        luminance(self.r, self.g, self.b)
+    }
+}
+
+#[inline]
+fn luminance(r: u8, g: u8, b: u8) -> u8 {
+    (0.299 * r as f64 + 0.587 * g as f64 + 0.114 * b as f64).round() as u8
+}
+
+/// Extracts the channel selected by `mask` from `pixel`, scaled to 8 bits.
+fn masked_channel(pixel: u32, mask: u32) -> u8 {
+    if mask == 0 {
+        return 0;
+    }
+
+    let shift = mask.trailing_zeros();
+    let max = mask >> shift;
+    let value = (pixel & mask) >> shift;
+
+    (value as f64 * 255.0 / max as f64).round() as u8
+}
+
+/// Headers of a new `width` × `height` image with `pallet_size` palette
+/// entries, and its pixel count. Fails with `Error::DimensionOverflow` if the
+/// pixel buffer or the BMP file size would not fit in 32 bits.
+fn new_headers(
+    width: u32,
+    height: u32,
+    bits_per_pixel: u16,
+    pallet_size: usize,
+) -> Result<(BmpFileHeader, BmpInfoHeader, usize)> {
+    let overflow = || Error::DimensionOverflow { width, height };
+    let pixel_count = width.checked_mul(height).ok_or_else(overflow)?;
+    let image_size = (BmpInfoHeader::stride(width, bits_per_pixel) as u64 * height as u64)
+        .try_into()
+        .map_err(|_| overflow())?;
+    let header_size =
+        BmpFileHeader::HEADER_SIZE + BmpInfoHeader::HEADER_SIZE + pallet_size as u32 * 4;
+    if width > i32::MAX as u32
+        || height > i32::MAX as u32
+        || header_size.checked_add(image_size).is_none()
+    {
+        return Err(overflow());
+    }
+
+    let header = BmpFileHeader::new(header_size, image_size);
+    let mut info = BmpInfoHeader::new(width as i32, height as i32, image_size);
+    info.bits_per_pixel = bits_per_pixel;
+
+    Ok((header, info, pixel_count as usize))
 }
 
 const PALLATE_SIZE: usize = 256;
@@ -125,25 +281,23 @@ pub struct Image {
 }
 
 impl Image {
-    pub fn new(width: u32, height: u32) -> Self {
+    /// Creates a black image. Fails with `Error::DimensionOverflow` if the
+    /// pixel buffer or the BMP file size would not fit in 32 bits.
+    pub fn new(width: u32, height: u32) -> Result<Self> {
+        let (header, info, pixel_count) = new_headers(width, height, 8, PALLATE_SIZE)?;
         let pallet = (0u8..=255)
             .map(|i| BmpColorPallet::new(i, i, i))
             .collect::<Vec<_>>()
             .try_into()
             .unwrap();
-        let data = vec![0; (width * height) as usize];
-        let header = BmpFileHeader::new(
-            BmpFileHeader::HEADER_SIZE + BmpInfoHeader::HEADER_SIZE + PALLATE_SIZE as u32 * 4,
-            data.len() as u32,
-        );
-        let info = BmpInfoHeader::new(width as i32, height as i32);
-
-        Image {
+        let data = vec![0; pixel_count];
+
+        Ok(Image {
             header,
             info,
             pallet,
             data,
-        }
+        })
     }
 
     #[inline]
@@ -156,6 +310,54 @@ impl Image {
         self.info.height as u32
     }
 
+    /// Records the physical pixel pitch (in meters) as the pixels-per-meter
+    /// resolution of the written file.
+    pub fn set_pixel_pitch(&mut self, pixel_pitch: f64) {
+        let pixels_per_meter = (1.0 / pixel_pitch).round() as i32;
+        self.info.x_pixels_per_meter = pixels_per_meter;
+        self.info.y_pixels_per_meter = pixels_per_meter;
+    }
+
+    /// Returns the pixel pitch (in meters) recorded in the file, if any.
+    pub fn pixel_pitch(&self) -> Option<f64> {
+        match self.info.x_pixels_per_meter {
+            pixels_per_meter if pixels_per_meter > 0 => Some(1.0 / pixels_per_meter as f64),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn bits_per_pixel(&self) -> u16 {
+        self.info.bits_per_pixel
+    }
+
+    /// Selects the depth the image is written with: 1, 4 or 8 bits per pixel.
+    /// Pixels keep their 8-bit gray level in memory and only the top
+    /// `bits_per_pixel` bits are stored, with an evenly spaced gray color
+    /// table, so encoders should produce levels that survive the truncation.
+    /// Fails with `Error::UnsupportedFormat` for other depths.
+    pub fn set_bits_per_pixel(&mut self, bits_per_pixel: u16) -> Result<()> {
+        if !matches!(bits_per_pixel, 1 | 4 | 8) {
+            return Err(Error::UnsupportedFormat(format!(
+                "BMP output with {} bits per pixel",
+                bits_per_pixel
+            )));
+        }
+
+        let colors = 1u32 << bits_per_pixel;
+        // Never larger than the 8-bit size checked in `new`.
+        let image_size =
+            (BmpInfoHeader::stride(self.width(), bits_per_pixel) * self.height() as usize) as u32;
+        let header_size = BmpFileHeader::HEADER_SIZE + BmpInfoHeader::HEADER_SIZE + colors * 4;
+
+        self.header = BmpFileHeader::new(header_size, image_size);
+        self.info.bits_per_pixel = bits_per_pixel;
+        self.info.image_size = image_size;
+        self.info.colors_used = if bits_per_pixel == 8 { 0 } else { colors };
+
+        Ok(())
+    }
+
     #[inline]
     pub fn set_pixel(&mut self, x: u32, y: u32, color: u8) {
         let index = (y * self.width() + x) as usize;
@@ -176,17 +378,271 @@ impl Image {
     pub fn to_writer<W: Write>(&self, destination: &mut W) -> io::Result<()> {
         self.header.to_writer(destination)?;
         self.info.to_writer(destination)?;
-        self.pallet
+
+        let bits_per_pixel = self.bits_per_pixel();
+        if bits_per_pixel == 8 {
+            self.pallet
+                .iter()
+                .try_for_each(|pallet| pallet.to_writer(destination))?;
+        } else {
+            let max = (1u32 << bits_per_pixel) - 1;
+            (0..=max)
+                .map(|i| (i * 255 / max) as u8)
+                .try_for_each(|i| BmpColorPallet::new(i, i, i).to_writer(destination))?;
+        }
+
+        // Rows are stored bottom-up, so row 0 of `data` (the top row, matching
+        // the `ndarray` row order) is written last.
+        let width = self.width() as usize;
+        let mut buffer = vec![0; BmpInfoHeader::stride(self.width(), bits_per_pixel)];
+        let per_byte = 8 / bits_per_pixel as usize;
+        for row in self.data.chunks_exact(width.max(1)).rev() {
+            buffer.fill(0);
+            for (i, &pixel) in row.iter().enumerate() {
+                let value = pixel >> (8 - bits_per_pixel);
+                let shift = 8 - bits_per_pixel as usize * (i % per_byte + 1);
+                buffer[i / per_byte] |= value << shift;
+            }
+            destination.write_all(&buffer)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        File::create(path)
+            .and_then(|mut file| self.to_writer(&mut file))
+            .map_err(|error| Error::from(error).with_path(path))
+    }
+
+    /// Reads an uncompressed 1, 4, 8, 24 or 32-bit BMP, converting the pixels to
+    /// the 8-bit grayscale buffer used by `Image`. Both bottom-up and top-down
+    /// (negative height) files are loaded with row 0 at the top. Fails with
+    /// `Error::InvalidImage` before allocating the image if the pixel data
+    /// would not fit in the file size given by the header.
+    pub fn from_reader<R: Read>(source: &mut R) -> Result<Self> {
+        Self::read(source, None)
+    }
+
+    /// See `from_reader`. The pixel data is checked against `stream_len`
+    /// instead of the file size in the header, if given.
+    fn read<R: Read>(source: &mut R, stream_len: Option<u64>) -> Result<Self> {
+        let header = BmpFileHeader::from_reader(source)?;
+        let (info, header_masks) = BmpInfoHeader::from_reader(source)?;
+        let header_size_error = || invalid_data("invalid BMP header size");
+        let mut consumed = BmpFileHeader::HEADER_SIZE
+            .checked_add(info.header_size)
+            .ok_or_else(header_size_error)?;
+
+        if info.width <= 0 || info.height == 0 || info.height == i32::MIN {
+            return Err(invalid_data("invalid BMP dimensions"));
+        }
+
+        let bits_per_pixel = info.bits_per_pixel;
+        let masks = match (info.compression, bits_per_pixel) {
+            (BmpInfoHeader::COMPRESSION_RGB, 1 | 4 | 8 | 24) => None,
+            (BmpInfoHeader::COMPRESSION_RGB, 32) => Some([0xff << 16, 0xff << 8, 0xff, 0]),
+            (BmpInfoHeader::COMPRESSION_BITFIELDS, 32) => match header_masks {
+                Some(masks) => Some(masks),
+                None => {
+                    consumed = consumed.checked_add(12).ok_or_else(header_size_error)?;
+                    Some([read_u32(source)?, read_u32(source)?, read_u32(source)?, 0])
+                }
+            },
+            (compression, bits_per_pixel) => {
+                return Err(Error::UnsupportedFormat(format!(
+                    "BMP with {} bits per pixel and compression {}",
+                    bits_per_pixel, compression
+                )))
+            }
+        };
+
+        let gray_pallet = if bits_per_pixel <= 8 {
+            let max_colors = 1u32 << bits_per_pixel;
+            let colors = match info.colors_used {
+                0 => max_colors,
+                colors if colors <= max_colors => colors,
+                _ => return Err(invalid_data("invalid BMP color table")),
+            };
+            consumed = consumed
+                .checked_add(colors * 4)
+                .ok_or_else(header_size_error)?;
+
+            let mut gray = vec![0; max_colors as usize];
+            for entry in gray.iter_mut().take(colors as usize) {
+                *entry = BmpColorPallet::from_reader(source)?.gray();
+            }
+            gray
+        } else {
+            Vec::new()
+        };
+
+        if header.data_offset < consumed {
+            return Err(invalid_data("invalid BMP data offset"));
+        }
+        skip(source, (header.data_offset - consumed) as u64)?;
+
+        let width = info.width as u32;
+        let height = info.height.unsigned_abs();
+        let top_down = info.height < 0;
+        let stride = BmpInfoHeader::stride(width, bits_per_pixel);
+        let available = stream_len
+            .unwrap_or(header.file_size as u64)
+            .saturating_sub(header.data_offset as u64);
+        if stride as u64 * height as u64 > available {
+            return Err(invalid_data("BMP pixel data exceeds the file size"));
+        }
+
+        let mut image = Image::new(width, height)?;
+        image.info.x_pixels_per_meter = info.x_pixels_per_meter;
+        image.info.y_pixels_per_meter = info.y_pixels_per_meter;
+
+        let mut row = vec![0; stride];
+        for file_row in 0..height {
+            source.read_exact(&mut row)?;
+            let y = if top_down {
+                file_row
+            } else {
+                height - 1 - file_row
+            };
+
+            for x in 0..width {
+                let i = x as usize;
+                let color = match bits_per_pixel {
+                    1 => gray_pallet[((row[i / 8] >> (7 - i % 8)) & 1) as usize],
+                    4 => gray_pallet[((row[i / 2] >> (4 - 4 * (i % 2))) & 0xf) as usize],
+                    8 => gray_pallet[row[i] as usize],
+                    24 => luminance(row[i * 3 + 2], row[i * 3 + 1], row[i * 3]),
+                    _ => {
+                        let [r, g, b, _] = masks.unwrap();
+                        let bytes = [row[i * 4], row[i * 4 + 1], row[i * 4 + 2], row[i * 4 + 3]];
+                        let pixel = u32::from_le_bytes(bytes);
+                        luminance(
+                            masked_channel(pixel, r),
+                            masked_channel(pixel, g),
+                            masked_channel(pixel, b),
+                        )
+                    }
+                };
+                image.set_pixel(x, y, color);
+            }
+        }
+
+        Ok(image)
+    }
+
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let read = || -> Result<Self> {
+            let file = File::open(path)?;
+            let stream_len = file.metadata()?.len();
+            Self::read(&mut BufReader::new(file), Some(stream_len))
+        };
+
+        read().map_err(|error| error.with_path(path))
+    }
+}
+
+/// 24-bit RGB image, e.g. the three channels of a color hologram for an SLM
+/// that shows them time-multiplexed from the red, green and blue bits of its
+/// video input.
+#[derive(Debug)]
+pub struct RgbImage {
+    header: BmpFileHeader,
+    info: BmpInfoHeader,
+    data: Vec<[u8; 3]>,
+}
+
+impl RgbImage {
+    /// Creates a black image. Fails with `Error::DimensionOverflow` if the
+    /// pixel buffer or the BMP file size would not fit in 32 bits.
+    pub fn new(width: u32, height: u32) -> Result<Self> {
+        let (header, info, pixel_count) = new_headers(width, height, 24, 0)?;
+
+        Ok(RgbImage {
+            header,
+            info,
+            data: vec![[0; 3]; pixel_count],
+        })
+    }
+
+    /// Combines three grayscale images of the same size into the red, green
+    /// and blue channels. The pixel pitch is taken from `red`.
+    pub fn from_channels(red: &Image, green: &Image, blue: &Image) -> Result<Self> {
+        let (width, height) = (red.width(), red.height());
+        if [green, blue]
             .iter()
-            .try_for_each(|pallet| pallet.to_writer(destination))?;
-        destination.write_all(&self.data)?;
+            .any(|image| image.width() != width || image.height() != height)
+        {
+            return Err(invalid_data("color channels differ in size"));
+        }
+
+        let mut image = RgbImage::new(width, height)?;
+        image.info.x_pixels_per_meter = red.info.x_pixels_per_meter;
+        image.info.y_pixels_per_meter = red.info.y_pixels_per_meter;
+        for (pixel, ((&r, &g), &b)) in image
+            .data
+            .iter_mut()
+            .zip(red.data.iter().zip(&green.data).zip(&blue.data))
+        {
+            *pixel = [r, g, b];
+        }
+
+        Ok(image)
+    }
+
+    #[inline]
+    pub fn width(&self) -> u32 {
+        self.info.width as u32
+    }
+
+    #[inline]
+    pub fn height(&self) -> u32 {
+        self.info.height as u32
+    }
+
+    /// Records the physical pixel pitch (in meters) as the pixels-per-meter
+    /// resolution of the written file.
+    pub fn set_pixel_pitch(&mut self, pixel_pitch: f64) {
+        let pixels_per_meter = (1.0 / pixel_pitch).round() as i32;
+        self.info.x_pixels_per_meter = pixels_per_meter;
+        self.info.y_pixels_per_meter = pixels_per_meter;
+    }
+
+    #[inline]
+    pub fn set_pixel(&mut self, x: u32, y: u32, color: [u8; 3]) {
+        let index = (y * self.width() + x) as usize;
+        self.data[index] = color;
+    }
+
+    #[inline]
+    pub fn get_pixel(&self, x: u32, y: u32) -> [u8; 3] {
+        let index = (y * self.width() + x) as usize;
+        self.data[index]
+    }
+
+    pub fn to_writer<W: Write>(&self, destination: &mut W) -> io::Result<()> {
+        self.header.to_writer(destination)?;
+        self.info.to_writer(destination)?;
+
+        let width = self.width() as usize;
+        let mut buffer = vec![0; BmpInfoHeader::stride(self.width(), 24)];
+        for row in self.data.chunks_exact(width.max(1)).rev() {
+            for (bytes, &[r, g, b]) in buffer.chunks_exact_mut(3).zip(row) {
+                bytes.copy_from_slice(&[b, g, r]);
+            }
+            destination.write_all(&buffer)?;
+        }
 
         Ok(())
     }
 
-    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
-        let mut file = File::create(path)?;
-        self.to_writer(&mut file)
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        File::create(path)
+            .and_then(|mut file| self.to_writer(&mut file))
+            .map_err(|error| Error::from(error).with_path(path))
     }
 }
 
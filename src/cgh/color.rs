@@ -0,0 +1,103 @@
+//! Color holograms, computed one wavelength at a time.
+//!
+//! The point-source processors work in physical units, so the three channels
+//! computed at their own wavelengths reconstruct the object at the same size.
+//! Fourier holograms of 2D targets (see `ifta`) do not: the pitch of the image
+//! plane is `λf / (N p)`, so each target has to be drawn at
+//! `ColorConfig::fourier_scale` of its size first, with `compensate_target`.
+
+use ndarray::Array2;
+
+use super::encoding::Encoder;
+use super::{generate_cgh, CghConfig, CghProcessor};
+
+use crate::bmp::{Image, RgbImage};
+use crate::error::Result;
+use crate::lut::TriFunc;
+use crate::object::Object3d;
+
+/// Red, green and blue laser lines commonly used for color holography.
+pub const RGB_WAVELENGTHS: [f64; 3] = [638.0e-9, 520.0e-9, 450.0e-9];
+
+#[derive(Debug, Clone, Copy)]
+pub struct ColorConfig {
+    /// Geometry shared by the channels. Its `wavelength` is ignored.
+    pub config: CghConfig,
+    /// Wavelengths of the red, green and blue channels, in meters.
+    pub wavelengths: [f64; 3],
+}
+
+impl ColorConfig {
+    /// Configuration of one channel (0 red, 1 green, 2 blue).
+    pub fn channel(&self, index: usize) -> CghConfig {
+        CghConfig {
+            wavelength: self.wavelengths[index],
+            ..self.config
+        }
+    }
+
+    /// Size, relative to the shortest wavelength, at which the target of a
+    /// channel must be drawn for the Fourier reconstructions to overlap.
+    pub fn fourier_scale(&self, index: usize) -> f64 {
+        let shortest = self
+            .wavelengths
+            .iter()
+            .copied()
+            .fold(f64::INFINITY, f64::min);
+        shortest / self.wavelengths[index]
+    }
+}
+
+/// Computes the red, green and blue holograms of `object`, each from the
+/// matching channel of its colors (see `Object3d::channel`).
+pub fn generate_color_cgh(
+    processor: &dyn CghProcessor,
+    encoder: &dyn Encoder,
+    tri_func: &(dyn TriFunc + Sync),
+    config: &ColorConfig,
+    object: &Object3d,
+) -> Result<[Image; 3]> {
+    let [red, green, blue] = [0, 1, 2].map(|index| {
+        generate_cgh(
+            processor,
+            encoder,
+            tri_func,
+            &config.channel(index),
+            &object.channel(index),
+        )
+    });
+
+    Ok([red?, green?, blue?])
+}
+
+/// Packs the three channel holograms into one 24-bit image.
+pub fn to_rgb_image(channels: &[Image; 3]) -> Result<RgbImage> {
+    let [red, green, blue] = channels;
+    RgbImage::from_channels(red, green, blue)
+}
+
+/// Shrinks (or enlarges) `target` by `scale` about its center, keeping its
+/// size, with bilinear interpolation. Samples falling outside are zero.
+pub fn compensate_target(target: &Array2<f64>, scale: f64) -> Array2<f64> {
+    let (height, width) = target.dim();
+    let center_y = (height as f64 - 1.0) / 2.0;
+    let center_x = (width as f64 - 1.0) / 2.0;
+
+    let sample = |y: isize, x: isize| -> f64 {
+        match y >= 0 && x >= 0 && (y as usize) < height && (x as usize) < width {
+            true => target[(y as usize, x as usize)],
+            false => 0.0,
+        }
+    };
+
+    Array2::from_shape_fn((height, width), |(y, x)| {
+        let source_y = center_y + (y as f64 - center_y) / scale;
+        let source_x = center_x + (x as f64 - center_x) / scale;
+        let (y0, x0) = (source_y.floor(), source_x.floor());
+        let (fy, fx) = (source_y - y0, source_x - x0);
+        let (y0, x0) = (y0 as isize, x0 as isize);
+
+        (1.0 - fy) * ((1.0 - fx) * sample(y0, x0) + fx * sample(y0, x0 + 1))
+            + fy * ((1.0 - fx) * sample(y0 + 1, x0) + fx * sample(y0 + 1, x0 + 1))
+    })
+}
@@ -0,0 +1,163 @@
+//! Error-diffusion encoders for SLMs with few gray levels.
+//!
+//! Pixels are quantized in serpentine order and the quantization error of
+//! each is spread over its not yet visited neighbours, pushing the noise to
+//! high spatial frequencies outside the reconstruction.
+
+use ndarray::Array2;
+use num::Complex;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+use std::ops::{Add, Mul, Sub};
+
+use super::encoding::Encoder;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Kernel {
+    #[default]
+    FloydSteinberg,
+    JarvisJudiceNinke,
+}
+
+impl Kernel {
+    /// Weights as `(dx, dy, weight)` for a left-to-right scan.
+    fn weights(&self) -> &'static [(isize, usize, f64)] {
+        match self {
+            Kernel::FloydSteinberg => &[
+                (1, 0, 7.0 / 16.0),
+                (-1, 1, 3.0 / 16.0),
+                (0, 1, 5.0 / 16.0),
+                (1, 1, 1.0 / 16.0),
+            ],
+            Kernel::JarvisJudiceNinke => &[
+                (1, 0, 7.0 / 48.0),
+                (2, 0, 5.0 / 48.0),
+                (-2, 1, 3.0 / 48.0),
+                (-1, 1, 5.0 / 48.0),
+                (0, 1, 7.0 / 48.0),
+                (1, 1, 5.0 / 48.0),
+                (2, 1, 3.0 / 48.0),
+                (-2, 2, 1.0 / 48.0),
+                (-1, 2, 3.0 / 48.0),
+                (0, 2, 5.0 / 48.0),
+                (1, 2, 3.0 / 48.0),
+                (2, 2, 1.0 / 48.0),
+            ],
+        }
+    }
+}
+
+/// Error diffusion of the real part of the field, mapped from its range onto
+/// `levels` evenly spaced amplitude levels. With two levels this is the
+/// diffused counterpart of `encoding::Binary`.
+#[derive(Debug, Clone, Copy)]
+pub struct ErrorDiffusion {
+    pub kernel: Kernel,
+    pub levels: u32,
+}
+
+impl Default for ErrorDiffusion {
+    fn default() -> Self {
+        ErrorDiffusion {
+            kernel: Kernel::default(),
+            levels: 2,
+        }
+    }
+}
+
+impl Encoder for ErrorDiffusion {
+    fn encode(&self, field: &Array2<Complex<f64>>) -> Array2<f64> {
+        assert!(self.levels >= 2, "error diffusion needs at least 2 levels");
+
+        let (min, max) = field.iter().fold((f64::MAX, f64::MIN), |(min, max), v| {
+            (min.min(v.re), max.max(v.re))
+        });
+        let range = if max > min { max - min } else { 1.0 };
+        let steps = (self.levels - 1) as f64;
+
+        let values = field.mapv(|complex| (complex.re - min) / range);
+        diffuse(values, self.kernel, |value| {
+            let level = (value * steps).round().clamp(0.0, steps) / steps;
+            (level, level)
+        })
+    }
+}
+
+/// Complex error diffusion for phase-only SLMs with `levels` evenly spaced
+/// phase levels. Each sample becomes the nearest unit phasor and the complex
+/// difference is diffused, so the amplitude of the field is carried by the
+/// surrounding phases instead of being discarded as in `encoding::Kinoform`.
+///
+/// The field is normalized to unit RMS amplitude beforehand.
+#[derive(Debug, Clone, Copy)]
+pub struct ComplexErrorDiffusion {
+    pub kernel: Kernel,
+    pub levels: u32,
+}
+
+impl Default for ComplexErrorDiffusion {
+    fn default() -> Self {
+        ComplexErrorDiffusion {
+            kernel: Kernel::default(),
+            levels: 16,
+        }
+    }
+}
+
+impl Encoder for ComplexErrorDiffusion {
+    fn encode(&self, field: &Array2<Complex<f64>>) -> Array2<f64> {
+        assert!(self.levels >= 2, "error diffusion needs at least 2 levels");
+
+        let power = field.iter().map(|complex| complex.norm_sqr()).sum::<f64>();
+        let rms = (power / field.len().max(1) as f64).sqrt();
+        let scale = if rms > 0.0 { 1.0 / rms } else { 1.0 };
+        let levels = self.levels as f64;
+
+        let values = field.mapv(|complex| complex * scale);
+        diffuse(values, self.kernel, |value| {
+            let step = (value.arg() / (2.0 * PI) * levels)
+                .round()
+                .rem_euclid(levels);
+            let phasor = Complex::from_polar(1.0, 2.0 * PI * step / levels);
+            (phasor, step / levels)
+        })
+    }
+}
+
+/// Runs error diffusion over `values`. `quantize` returns the value a sample
+/// is replaced with, from which the error is computed, and the level written
+/// to the output.
+fn diffuse<T, F>(mut values: Array2<T>, kernel: Kernel, quantize: F) -> Array2<f64>
+where
+    T: Copy + Add<Output = T> + Sub<Output = T> + Mul<f64, Output = T>,
+    F: Fn(T) -> (T, f64),
+{
+    let (height, width) = values.dim();
+    let mut levels = Array2::zeros((height, width));
+
+    for y in 0..height {
+        let reverse = y % 2 == 1;
+
+        for i in 0..width {
+            let x = if reverse { width - 1 - i } else { i };
+            let value = values[(y, x)];
+            let (quantized, level) = quantize(value);
+            levels[(y, x)] = level;
+
+            let error = value - quantized;
+            for &(dx, dy, weight) in kernel.weights() {
+                let dx = if reverse { -dx } else { dx };
+                let (nx, ny) = (x as isize + dx, y + dy);
+                if nx < 0 || nx >= width as isize || ny >= height {
+                    continue;
+                }
+
+                let neighbour = &mut values[(ny, nx as usize)];
+                *neighbour = *neighbour + error * weight;
+            }
+        }
+    }
+
+    levels
+}
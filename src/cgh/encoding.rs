@@ -0,0 +1,103 @@
+//! Conversion of the complex object field into what the SLM displays.
+//!
+//! An `Encoder` maps the field to levels in `0.0..=1.0`; `generate_cgh`
+//! quantizes them into the gray levels of the output image.
+
+use ndarray::Array2;
+use num::Complex;
+use std::f64::consts::PI;
+
+pub trait Encoder {
+    fn encode(&self, field: &Array2<Complex<f64>>) -> Array2<f64>;
+}
+
+/// Phase-only kinoform. The phase `0..2π` maps linearly onto `0..1`
+/// independent of the data, so gray level `g` of an 8-bit image always shows
+/// the phase `2πg / 256`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Kinoform;
+
+impl Encoder for Kinoform {
+    fn encode(&self, field: &Array2<Complex<f64>>) -> Array2<f64> {
+        field.mapv(|complex| complex.arg().rem_euclid(2.0 * PI) / (2.0 * PI))
+    }
+}
+
+/// Amplitude hologram of the real part of the field, i.e. the interference
+/// term with an on-axis plane reference wave, lifted by the reference bias.
+#[derive(Debug, Clone, Copy)]
+pub struct Amplitude {
+    /// Reference amplitude relative to the largest object amplitude. With
+    /// 1.0 the real part spans exactly `0..1`; larger values trade contrast
+    /// for headroom, smaller ones clip.
+    pub bias: f64,
+}
+
+impl Default for Amplitude {
+    fn default() -> Self {
+        Amplitude { bias: 1.0 }
+    }
+}
+
+impl Encoder for Amplitude {
+    fn encode(&self, field: &Array2<Complex<f64>>) -> Array2<f64> {
+        let scale = self.bias * max_norm(field);
+        if scale == 0.0 {
+            return Array2::from_elem(field.dim(), 0.5);
+        }
+
+        field.mapv(|complex| (0.5 * (1.0 + complex.re / scale)).clamp(0.0, 1.0))
+    }
+}
+
+/// Binary amplitude hologram: the real part thresholded at the midpoint of
+/// its range.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Binary;
+
+impl Encoder for Binary {
+    fn encode(&self, field: &Array2<Complex<f64>>) -> Array2<f64> {
+        let (min, max) = field.iter().fold((f64::MAX, f64::MIN), |(min, max), v| {
+            (min.min(v.re), max.max(v.re))
+        });
+        let mid = (max + min) / 2.0;
+
+        field.mapv(|complex| if complex.re > mid { 1.0 } else { 0.0 })
+    }
+}
+
+/// Double-phase amplitude encoding. The field `A exp(iφ)`, with `A`
+/// normalized to `0..1`, is the mean of the two unit phasors at
+/// `φ ± acos(A)`, which are laid out on a checkerboard.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DoublePhase;
+
+impl Encoder for DoublePhase {
+    fn encode(&self, field: &Array2<Complex<f64>>) -> Array2<f64> {
+        let max = max_norm(field);
+        let max = if max > 0.0 { max } else { 1.0 };
+
+        Array2::from_shape_fn(field.dim(), |(y, x)| {
+            let (amplitude, phase) = field[(y, x)].to_polar();
+            let offset = (amplitude / max).min(1.0).acos();
+            let theta = match (x + y) % 2 {
+                0 => phase + offset,
+                _ => phase - offset,
+            };
+
+            theta.rem_euclid(2.0 * PI) / (2.0 * PI)
+        })
+    }
+}
+
+/// Phase of each sample of `field` in radians, in `-π..=π`.
+pub fn phase(field: &Array2<Complex<f64>>) -> Array2<f64> {
+    field.mapv(|complex| f64::atan2(complex.im, complex.re))
+}
+
+fn max_norm(field: &Array2<Complex<f64>>) -> f64 {
+    field
+        .iter()
+        .map(|complex| complex.norm())
+        .fold(0.0, f64::max)
+}
@@ -1,66 +1,107 @@
+use ndarray::parallel::prelude::*;
 use ndarray::Array2;
-use ndarray::Zip;
 use num::{Complex, Zero};
+use std::f64::consts::PI;
 
+use super::occlusion::Occlusion;
 use super::{CghConfig, CghProcessor};
 
 use crate::lut::TriFunc;
 use crate::object::Object3d;
-use crate::point::Point;
 
+/// Sums the paraxial waves of the points over row `$y`. Each point fills a
+/// buffer with its phase and weight at every pixel of the row, evaluated with
+/// one `TriFunc::eval_batch` call.
 macro_rules! process {
-    ($x:expr, $y:expr, $pixel:expr, $tri_func:expr, $config:expr, $object:expr) => {{
-        let mut complex = Complex::zero();
+    ($y:expr, $row:expr, $tri_func:expr, $config:expr, $object:expr, $visibility:expr) => {{
+        let width = $row.len();
+        let mut theta = vec![0.0; width];
+        let mut weight = vec![0.0; width];
+        let mut wave = vec![Complex::zero(); width];
+        let y = $y as f64;
 
-        for &point in $object.points() {
+        let points = $object.points().iter().zip($object.amplitudes());
+        for (index, ((&point, &amplitude), &phase)) in points.zip($object.phases()).enumerate() {
+            let visible = |x| $visibility.is_none_or(|visibility| visibility.block(x, $y)[index]);
             let point = (point * $config.scalar) + $config.offset;
-            let pixel_point = Point::new($x as f64, $y as f64, 0.0);
-            let distance = point.z
-                + ((pixel_point.x - point.x).powi(2) + (pixel_point.y - point.y).powi(2))
-                    / (2.0 * point.z);
+            let phase = phase / (2.0 * PI);
+            let dy2 = (y - point.y).powi(2);
 
-            let theta = $config.pixel_pitch * distance / $config.wavelength;
-            complex += Complex::new($tri_func.cos(theta), $tri_func.sin(theta));
-        }
+            let buffers = theta.iter_mut().zip(weight.iter_mut());
+            for (x, (theta, weight)) in buffers.enumerate() {
+                let distance = point.z + ((x as f64 - point.x).powi(2) + dy2) / (2.0 * point.z);
+                *theta = $config.pixel_pitch * distance / $config.wavelength + phase;
+                *weight = match visible(x) {
+                    true => amplitude,
+                    false => 0.0,
+                };
+            }
 
-        *$pixel = f64::atan2(complex.im, complex.re);
+            $tri_func.eval_batch(&theta, &mut wave);
+            for ((pixel, &weight), &wave) in $row.iter_mut().zip(&weight).zip(&wave) {
+                *pixel += weight * wave;
+            }
+        }
     }};
 }
 
-pub struct CghProcessorFresnel;
+#[derive(Default)]
+pub struct CghProcessorFresnel {
+    /// Hidden-surface removal; `None` sums every point into every pixel.
+    pub occlusion: Option<Occlusion>,
+}
 
 impl CghProcessor for CghProcessorFresnel {
-    fn process(
+    fn field(
         &self,
         tri_func: &(dyn TriFunc + Sync),
         config: &CghConfig,
         object: &Object3d,
-    ) -> Array2<f64> {
-        let mut array =
-            Array2::<f64>::zeros((config.image_height as usize, config.image_width as usize));
+    ) -> Array2<Complex<f64>> {
+        let visibility = self
+            .occlusion
+            .map(|occlusion| occlusion.visibility(config, object));
+        let mut array = Array2::<Complex<f64>>::zeros((
+            config.image_height as usize,
+            config.image_width as usize,
+        ));
 
-        array
-            .indexed_iter_mut()
-            .for_each(|((y, x), pixel)| process!(x, y, pixel, tri_func, config, object));
+        array.outer_iter_mut().enumerate().for_each(|(y, mut row)| {
+            process!(y, row, tri_func, config, object, visibility.as_ref())
+        });
 
         array
     }
 }
 
-pub struct CghProcessorFresnelParallel;
+#[derive(Default)]
+pub struct CghProcessorFresnelParallel {
+    /// Hidden-surface removal; `None` sums every point into every pixel.
+    pub occlusion: Option<Occlusion>,
+}
 
 impl CghProcessor for CghProcessorFresnelParallel {
-    fn process(
+    fn field(
         &self,
         tri_func: &(dyn TriFunc + Sync),
         config: &CghConfig,
         object: &Object3d,
-    ) -> Array2<f64> {
-        let mut array =
-            Array2::<f64>::zeros((config.image_height as usize, config.image_width as usize));
+    ) -> Array2<Complex<f64>> {
+        let visibility = self
+            .occlusion
+            .map(|occlusion| occlusion.visibility(config, object));
+        let mut array = Array2::<Complex<f64>>::zeros((
+            config.image_height as usize,
+            config.image_width as usize,
+        ));
 
-        Zip::indexed(&mut array)
-            .par_for_each(|(y, x), pixel| process!(x, y, pixel, tri_func, config, object));
+        array
+            .outer_iter_mut()
+            .into_par_iter()
+            .enumerate()
+            .for_each(|(y, mut row)| {
+                process!(y, row, tri_func, config, object, visibility.as_ref())
+            });
 
         array
     }
@@ -0,0 +1,137 @@
+use ndarray::Array2;
+use num::{Complex, Zero};
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+use super::{CghConfig, CghProcessor};
+
+use crate::lut::TriFunc;
+use crate::object::Object3d;
+use crate::propagation::{Method, Propagator};
+use crate::random::SplitMix64;
+
+/// Layer-based method, suited to dense objects such as RGB-D images (see
+/// `rgbd::RgbdImage::to_object`).
+///
+/// The depth range of the object is split into `layers` equal slices. Points
+/// are rounded to the nearest hologram pixel on the middle plane of their
+/// slice, keeping the axial phase of their true depth, and each slice is
+/// propagated to the hologram with an FFT-based method on a twice zero-padded
+/// grid. Points falling outside the hologram are dropped.
+pub struct CghProcessorLayer {
+    pub layers: usize,
+    pub method: LayerMethod,
+    /// Seed of random phases given to every pixel of every layer in place of
+    /// the phases of the points, to diffuse the light of flat regions.
+    pub random_phase: Option<u64>,
+}
+
+/// The propagation methods of `Method` that keep the pixel pitch, so that
+/// the layers can be summed on the hologram grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LayerMethod {
+    #[serde(rename = "asm")]
+    AngularSpectrum,
+    #[serde(rename = "fresnel")]
+    FresnelConvolution,
+}
+
+impl From<LayerMethod> for Method {
+    fn from(method: LayerMethod) -> Self {
+        match method {
+            LayerMethod::AngularSpectrum => Method::AngularSpectrum,
+            LayerMethod::FresnelConvolution => Method::FresnelConvolution,
+        }
+    }
+}
+
+impl Default for CghProcessorLayer {
+    fn default() -> Self {
+        CghProcessorLayer {
+            layers: 32,
+            method: LayerMethod::AngularSpectrum,
+            random_phase: None,
+        }
+    }
+}
+
+impl CghProcessorLayer {
+    /// Returns the field of each non-empty layer on its own plane, with the
+    /// layer depth in meters.
+    fn slice(&self, config: &CghConfig, object: &Object3d) -> Vec<(Array2<Complex<f64>>, f64)> {
+        assert!(self.layers > 0, "at least one layer is required");
+        let (width, height) = (config.image_width as usize, config.image_height as usize);
+
+        let points: Vec<_> = object
+            .points()
+            .iter()
+            .map(|&point| (point * config.scalar) + config.offset)
+            .collect();
+        let (near, far) = points
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(near, far), p| {
+                (near.min(p.z), far.max(p.z))
+            });
+        let step = match far > near {
+            true => (far - near) / self.layers as f64,
+            false => 0.0,
+        };
+
+        let mut layers = vec![None; self.layers];
+        let samples = points.iter().zip(object.amplitudes()).zip(object.phases());
+        for ((point, &amplitude), &phase) in samples {
+            let (x, y) = (point.x.round(), point.y.round());
+            if x < 0.0 || y < 0.0 || x >= width as f64 || y >= height as f64 {
+                continue;
+            }
+
+            let index = match step > 0.0 {
+                true => (((point.z - near) / step) as usize).min(self.layers - 1),
+                false => 0,
+            };
+            // Moving the point onto the middle of its slice would change its
+            // axial phase by k times the shift; that much is added back.
+            let shift = point.z - (near + (index as f64 + 0.5) * step);
+            let phase = phase + 2.0 * PI * shift * config.pixel_pitch / config.wavelength;
+            let layer = layers[index].get_or_insert_with(|| Array2::zeros((height, width)));
+            layer[(y as usize, x as usize)] += Complex::from_polar(amplitude, phase);
+        }
+
+        let mut random = self.random_phase.map(SplitMix64::new);
+        layers
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, layer)| {
+                let mut layer: Array2<Complex<f64>> = layer?;
+                if let Some(random) = &mut random {
+                    layer.mapv_inplace(|value| match value.is_zero() {
+                        true => value,
+                        false => Complex::from_polar(value.norm(), 2.0 * PI * random.next_f64()),
+                    });
+                }
+
+                let depth = near + (index as f64 + 0.5) * step;
+                Some((layer, depth * config.pixel_pitch))
+            })
+            .collect()
+    }
+}
+
+impl CghProcessor for CghProcessorLayer {
+    fn field(
+        &self,
+        _tri_func: &(dyn TriFunc + Sync),
+        config: &CghConfig,
+        object: &Object3d,
+    ) -> Array2<Complex<f64>> {
+        let (width, height) = (config.image_width as usize, config.image_height as usize);
+        let propagator = Propagator::new(config.pixel_pitch, config.wavelength);
+
+        let mut field = Array2::<Complex<f64>>::zeros((height, width));
+        for (layer, depth) in self.slice(config, object) {
+            field += &propagator.propagate(&layer, depth, self.method.into());
+        }
+
+        field
+    }
+}
@@ -1,13 +1,29 @@
+pub mod color;
+pub mod diffusion;
+pub mod encoding;
 pub mod fresnel;
+pub mod layer;
 pub mod naive;
+pub mod occlusion;
+pub mod polygon;
 pub mod recurrence;
+pub mod reference;
+pub mod simd;
+pub mod wrp;
 
 use ndarray::Array2;
+use num::Complex;
+use serde::{Deserialize, Serialize};
+
+use self::encoding::Encoder;
+use self::reference::Reference;
 
 use crate::bmp::Image;
+use crate::error::Result;
 use crate::lut::TriFunc;
 use crate::object::Object3d;
 use crate::point::Point;
+use crate::raster::{Pixels, Raster};
 
 #[derive(Debug, Clone, Copy)]
 pub struct CghConfig {
@@ -17,35 +33,137 @@ pub struct CghConfig {
     pub wavelength: f64,
     pub offset: Point,
     pub scalar: f64,
+    /// Reference wave to interfere the object wave with. `None` encodes the
+    /// object field itself.
+    pub reference: Option<Reference>,
 }
 
 pub trait CghProcessor {
+    /// Complex object field on the hologram plane.
+    fn field(
+        &self,
+        tri_func: &(dyn TriFunc + Sync),
+        config: &CghConfig,
+        object: &Object3d,
+    ) -> Array2<Complex<f64>>;
+
+    /// Phase of the object field in radians, in `-π..=π`.
     fn process(
         &self,
         tri_func: &(dyn TriFunc + Sync),
         config: &CghConfig,
         object: &Object3d,
-    ) -> Array2<f64>;
+    ) -> Array2<f64> {
+        encoding::phase(&self.field(tri_func, config, object))
+    }
+
+    /// Field handed to the encoder: the object field, or the real-valued
+    /// interference pattern with `config.reference` when one is set.
+    fn hologram(
+        &self,
+        tri_func: &(dyn TriFunc + Sync),
+        config: &CghConfig,
+        object: &Object3d,
+    ) -> Array2<Complex<f64>> {
+        let field = self.field(tri_func, config, object);
+
+        match &config.reference {
+            Some(reference) => reference
+                .interfere(&field, tri_func, config)
+                .mapv(Complex::from),
+            None => field,
+        }
+    }
 }
 
 pub fn generate_cgh(
     processor: &dyn CghProcessor,
+    encoder: &dyn Encoder,
     tri_func: &(dyn TriFunc + Sync),
     config: &CghConfig,
     object: &Object3d,
-) -> Image {
-    let array = processor.process(tri_func, config, object);
+) -> Result<Image> {
+    let field = processor.hologram(tri_func, config, object);
 
-    let (min, max) = array.iter().fold((f64::MAX, f64::MIN), |(min, max), &v| {
-        (min.min(v), max.max(v))
-    });
+    encode_image(encoder, &field, config.pixel_pitch)
+}
 
-    let mut image = Image::new(config.image_width as u32, config.image_height as u32);
+/// Encodes a hologram-plane field computed by other means into an 8-bit
+/// image, as `generate_cgh` does for the field of a processor.
+pub fn encode_image(
+    encoder: &dyn Encoder,
+    field: &Array2<Complex<f64>>,
+    pixel_pitch: f64,
+) -> Result<Image> {
+    let array = encoder.encode(field);
+    let (height, width) = array.dim();
+
+    let mut image = Image::new(width as u32, height as u32)?;
+    image.set_pixel_pitch(pixel_pitch);
 
     for ((y, x), &pixel) in array.indexed_iter() {
-        let v = (255.0 * (pixel - min) / (max - min)) as u8;
-        image.set_pixel(x as u32, y as u32, v);
+        image.set_pixel(x as u32, y as u32, quantize(pixel, 8) as u8);
     }
 
-    image
+    Ok(image)
+}
+
+/// Sample format of the image produced by `generate_cgh_raster`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SampleDepth {
+    /// Encoded levels quantized to 0..=255.
+    #[default]
+    #[serde(rename = "8")]
+    Eight,
+    /// Encoded levels quantized to 0..=65535.
+    #[serde(rename = "16")]
+    Sixteen,
+    /// The raw phase of the object field in radians, from
+    /// `CghProcessor::process`. The encoder and the reference wave are not
+    /// applied.
+    #[serde(rename = "float")]
+    Float,
+    /// The encoded levels as they are, in 0.0..=1.0.
+    #[serde(rename = "float-level")]
+    FloatLevel,
+}
+
+pub fn generate_cgh_raster(
+    processor: &dyn CghProcessor,
+    encoder: &dyn Encoder,
+    tri_func: &(dyn TriFunc + Sync),
+    config: &CghConfig,
+    object: &Object3d,
+    depth: SampleDepth,
+) -> Raster {
+    let pixels = match depth {
+        SampleDepth::Float => {
+            let phase = processor.process(tri_func, config, object);
+            Pixels::GrayF32(phase.iter().map(|&v| v as f32).collect())
+        }
+        _ => {
+            let field = processor.hologram(tri_func, config, object);
+            let array = encoder.encode(&field);
+            let levels = array.iter().copied();
+
+            match depth {
+                SampleDepth::Eight => Pixels::Gray8(levels.map(|v| quantize(v, 8) as u8).collect()),
+                SampleDepth::Sixteen => {
+                    Pixels::Gray16(levels.map(|v| quantize(v, 16) as u16).collect())
+                }
+                _ => Pixels::GrayF32(levels.map(|v| v as f32).collect()),
+            }
+        }
+    };
+
+    let mut raster = Raster::new(config.image_width, config.image_height, pixels);
+    raster.set_pixel_pitch(config.pixel_pitch);
+
+    raster
+}
+
+/// Maps a level in `0.0..=1.0` onto `2^bits` equal steps.
+fn quantize(level: f64, bits: u32) -> u32 {
+    let steps = (1u32 << bits) as f64;
+    (level * steps).floor().clamp(0.0, steps - 1.0) as u32
 }
@@ -0,0 +1,212 @@
+use ndarray::Array2;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::CghConfig;
+
+use crate::object::Object3d;
+use crate::point::Point;
+
+/// Upper bound on the z-buffer size along each axis.
+const MAX_BINS: usize = 1024;
+
+/// Hidden-surface removal for the point-source processors.
+///
+/// The hologram is split into square blocks. From the center of each block
+/// the object is drawn into a z-buffer in perspective: the triangles of
+/// `Object3d::faces` as solid surfaces or, for point clouds, every point as a
+/// disk of `point_radius`. A point then contributes to the block only if
+/// nothing in its direction is nearer by more than twice that radius, the
+/// tolerance that keeps neighbouring points of a slanted surface from hiding
+/// each other. Surfaces seen at grazing angles may still lose points in point
+/// clouds; meshes (see `Object3d::sample_faces`) do not suffer from this.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Occlusion {
+    /// Side of the blocks, in pixels.
+    pub block_size: usize,
+    /// Radius of the disk each point of a point cloud hides, in object units
+    /// (before `CghConfig::scalar`). About the point spacing closes the gaps
+    /// of a sampled surface.
+    pub point_radius: f64,
+}
+
+impl Default for Occlusion {
+    fn default() -> Self {
+        Occlusion {
+            block_size: 64,
+            point_radius: 1.0,
+        }
+    }
+}
+
+/// Visibility of every point from every block of the hologram.
+pub(super) struct Visibility {
+    block_size: usize,
+    columns: usize,
+    points: usize,
+    visible: Vec<bool>,
+}
+
+impl Visibility {
+    /// Visibility flags of the points for the block containing pixel
+    /// `(x, y)`.
+    #[inline]
+    pub(super) fn block(&self, x: usize, y: usize) -> &[bool] {
+        let index = (y / self.block_size) * self.columns + x / self.block_size;
+        &self.visible[index * self.points..(index + 1) * self.points]
+    }
+}
+
+impl Occlusion {
+    pub(super) fn visibility(&self, config: &CghConfig, object: &Object3d) -> Visibility {
+        assert!(self.block_size > 0, "block size must be positive");
+        let (width, height) = (config.image_width as usize, config.image_height as usize);
+        let columns = width.div_ceil(self.block_size);
+        let rows = height.div_ceil(self.block_size);
+
+        let points: Vec<_> = object
+            .points()
+            .iter()
+            .map(|&point| (point * config.scalar) + config.offset)
+            .collect();
+        let radius = self.point_radius * config.scalar.abs();
+
+        let visible = (0..rows * columns)
+            .into_par_iter()
+            .flat_map_iter(|index| {
+                let (row, column) = (index / columns, index % columns);
+                let center = |start: usize, len: usize| {
+                    let end = (start + self.block_size).min(len);
+                    (start + end) as f64 / 2.0 - 0.5
+                };
+                let view = [
+                    center(column * self.block_size, width),
+                    center(row * self.block_size, height),
+                ];
+                visible_from(view, &points, object.faces(), radius)
+            })
+            .collect();
+
+        Visibility {
+            block_size: self.block_size,
+            columns,
+            points: points.len(),
+            visible,
+        }
+    }
+}
+
+/// Runs the z-buffer test from `view` on the hologram plane. Points at or
+/// behind the hologram plane are left visible.
+fn visible_from(view: [f64; 2], points: &[Point], faces: &[[usize; 3]], radius: f64) -> Vec<bool> {
+    // Perspective projection onto the plane one unit in front of the view.
+    let project = |point: &Point| [(point.x - view[0]) / point.z, (point.y - view[1]) / point.z];
+    let in_front = |point: &Point| point.z > 0.0;
+
+    let mut min = [f64::INFINITY; 2];
+    let mut max = [f64::NEG_INFINITY; 2];
+    let mut far = 0.0_f64;
+    for point in points.iter().filter(|point| in_front(point)) {
+        let [u, v] = project(point);
+        let extent = radius / point.z;
+        min = [min[0].min(u - extent), min[1].min(v - extent)];
+        max = [max[0].max(u + extent), max[1].max(v + extent)];
+        far = far.max(point.z);
+    }
+    if far == 0.0 {
+        return vec![true; points.len()];
+    }
+
+    // Bins of a quarter of the smallest disk radius, so that the depth of
+    // steep faces is sampled close enough to the points on them.
+    let span = (max[0] - min[0]).max(max[1] - min[1]);
+    let bin = (radius / (4.0 * far))
+        .max(span / MAX_BINS as f64)
+        .max(f64::MIN_POSITIVE);
+    let columns = ((max[0] - min[0]) / bin) as usize + 1;
+    let rows = ((max[1] - min[1]) / bin) as usize + 1;
+    let to_bin = |value: f64, axis: usize, len: usize| {
+        (((value - min[axis]) / bin).max(0.0) as usize).min(len - 1)
+    };
+    let bin_center = |index: usize, axis: usize| min[axis] + (index as f64 + 0.5) * bin;
+
+    let mut depth = Array2::from_elem((rows, columns), f64::INFINITY);
+
+    let splats = match faces.is_empty() {
+        true => points,
+        false => &[],
+    };
+    for point in splats.iter().filter(|point| in_front(point)) {
+        let [u, v] = project(point);
+        let extent = radius / point.z;
+        let (row, column) = (to_bin(v, 1, rows), to_bin(u, 0, columns));
+        depth[(row, column)] = depth[(row, column)].min(point.z);
+
+        for row in to_bin(v - extent, 1, rows)..=to_bin(v + extent, 1, rows) {
+            for column in to_bin(u - extent, 0, columns)..=to_bin(u + extent, 0, columns) {
+                let du = bin_center(column, 0) - u;
+                let dv = bin_center(row, 1) - v;
+                if du.powi(2) + dv.powi(2) <= extent.powi(2) {
+                    depth[(row, column)] = depth[(row, column)].min(point.z);
+                }
+            }
+        }
+    }
+
+    for face in faces {
+        let vertices = face.map(|index| points[index]);
+        if !vertices.iter().all(in_front) {
+            continue;
+        }
+        let projected = vertices.map(|vertex| project(&vertex));
+        let [a, b, c] = projected;
+        let area = (b[0] - a[0]) * (c[1] - a[1]) - (c[0] - a[0]) * (b[1] - a[1]);
+        if area == 0.0 {
+            continue;
+        }
+
+        let lower = |axis: usize| {
+            projected
+                .iter()
+                .map(|p| p[axis])
+                .fold(f64::INFINITY, f64::min)
+        };
+        let upper = |axis: usize| {
+            projected
+                .iter()
+                .map(|p| p[axis])
+                .fold(f64::NEG_INFINITY, f64::max)
+        };
+
+        for row in to_bin(lower(1), 1, rows)..=to_bin(upper(1), 1, rows) {
+            for column in to_bin(lower(0), 0, columns)..=to_bin(upper(0), 0, columns) {
+                let p = [bin_center(column, 0), bin_center(row, 1)];
+                // Barycentric coordinates of the bin center.
+                let weights = [
+                    ((b[0] - p[0]) * (c[1] - p[1]) - (c[0] - p[0]) * (b[1] - p[1])) / area,
+                    ((c[0] - p[0]) * (a[1] - p[1]) - (a[0] - p[0]) * (c[1] - p[1])) / area,
+                    ((a[0] - p[0]) * (b[1] - p[1]) - (b[0] - p[0]) * (a[1] - p[1])) / area,
+                ];
+                if weights.iter().any(|&weight| weight < 0.0) {
+                    continue;
+                }
+
+                // The reciprocal depth is affine in the projection.
+                let inverse = (0..3).map(|i| weights[i] / vertices[i].z).sum::<f64>();
+                depth[(row, column)] = depth[(row, column)].min(1.0 / inverse);
+            }
+        }
+    }
+
+    points
+        .iter()
+        .map(|point| {
+            if !in_front(point) {
+                return true;
+            }
+            let [u, v] = project(point);
+            point.z <= depth[(to_bin(v, 1, rows), to_bin(u, 0, columns))] + 2.0 * radius
+        })
+        .collect()
+}
@@ -0,0 +1,297 @@
+use ndarray::parallel::prelude::*;
+use ndarray::{Array2, Axis};
+use num::Complex;
+use std::f64::consts::PI;
+
+use super::{CghConfig, CghProcessor};
+
+use crate::fft::{self, Direction};
+use crate::lut::TriFunc;
+use crate::object::Object3d;
+use crate::point::Point;
+
+/// Polygon-based method for meshes (see `Object3d::with_faces`).
+///
+/// The angular spectrum of every triangle on the hologram plane is obtained
+/// analytically from that of the reference triangle (0, 0), (1, 0), (1, 1)
+/// through the affine map onto the triangle, and all spectra are summed
+/// before a single inverse FFT. Each triangle is a flat-shaded Lambertian
+/// surface carrying a linear phase that aims the light of the mesh at the
+/// hologram center; its spectrum is restricted to the plane waves that land on the
+/// hologram, which also keeps the FFT free of wrap-around.
+///
+/// The initial phase of a triangle is that of its first vertex, so
+/// `Object3d::with_random_phases` diffuses the faces against each other.
+/// Apart from back-face culling, hidden surfaces are not removed.
+///
+/// Only faces are drawn: the field of an object without faces is zero, and
+/// `Job` rejects such objects for this processor.
+pub struct CghProcessorPolygon {
+    /// Direction towards the light source, in object coordinates. A zero
+    /// vector turns the directional light off, leaving only `ambient`.
+    pub light: Point,
+    /// Share of the intensity that does not depend on the light direction.
+    pub ambient: f64,
+    /// Skips triangles whose front, the side from which the vertices run
+    /// counterclockwise, faces away from the hologram. Without culling both
+    /// sides are shown and shaded as the side facing the hologram.
+    pub cull_back_faces: bool,
+}
+
+impl Default for CghProcessorPolygon {
+    fn default() -> Self {
+        CghProcessorPolygon {
+            light: Point::new(0.0, 0.0, -1.0),
+            ambient: 0.1,
+            cull_back_faces: true,
+        }
+    }
+}
+
+/// A triangle in meters, with everything needed to evaluate its spectrum.
+struct Triangle {
+    origin: [f64; 3],
+    edges: [[f64; 3]; 2],
+    /// Amplitude times the Jacobian of the affine map.
+    weight: f64,
+    phase: f64,
+    /// Spatial frequency of the linear surface phase.
+    carrier: [f64; 3],
+    /// Frequency ranges (as signed FFT bins) of the plane waves that can
+    /// land on the hologram.
+    bins_x: (isize, isize),
+    bins_y: (isize, isize),
+    /// Range of `fx / w` and `fy / w` landing on the hologram.
+    slopes_x: (f64, f64),
+    slopes_y: (f64, f64),
+}
+
+impl CghProcessorPolygon {
+    fn triangles(&self, config: &CghConfig, object: &Object3d) -> Vec<Triangle> {
+        let pitch = config.pixel_pitch;
+        let wavelength = config.wavelength;
+        let (width, height) = (config.image_width as usize, config.image_height as usize);
+        let size = [width as f64 * pitch, height as f64 * pitch];
+        let center = [size[0] / 2.0, size[1] / 2.0, 0.0];
+        let light = [self.light.x, self.light.y, self.light.z];
+        let light = match norm(light) > 0.0 {
+            true => normalize(light),
+            false => [0.0; 3],
+        };
+
+        let position = |index: usize| {
+            let point = (object.points()[index] * config.scalar) + config.offset;
+            [point.x * pitch, point.y * pitch, point.z * pitch]
+        };
+
+        // One carrier for the whole mesh keeps the surface phase continuous
+        // across shared edges.
+        let vertices = object.faces().iter().flatten();
+        let count = object.faces().len() as f64 * 3.0;
+        let object_center = vertices
+            .map(|&index| position(index))
+            .fold([0.0; 3], |sum, p| {
+                [sum[0] + p[0], sum[1] + p[1], sum[2] + p[2]]
+            })
+            .map(|sum| sum / count);
+        let carrier = normalize(sub(center, object_center)).map(|d| d / wavelength);
+
+        object
+            .faces()
+            .iter()
+            .filter_map(|&[a, b, c]| {
+                let vertices = [position(a), position(b), position(c)];
+                let edges = [sub(vertices[1], vertices[0]), sub(vertices[2], vertices[1])];
+                let normal = cross(edges[0], edges[1]);
+                let jacobian = norm(normal);
+                let centroid = [0, 1, 2].map(|i| vertices.iter().map(|v| v[i]).sum::<f64>() / 3.0);
+                let back_face = normal[2] >= 0.0;
+                if jacobian == 0.0 || centroid[2] <= 0.0 || (self.cull_back_faces && back_face) {
+                    return None;
+                }
+
+                // Shade the side facing the hologram (-z).
+                let sign = if back_face { -1.0 } else { 1.0 };
+                let normal = normal.map(|n| sign * n / jacobian);
+                let diffuse = dot(normal, light).max(0.0);
+                let intensity = self.ambient + (1.0 - self.ambient) * diffuse;
+                let amplitude = [a, b, c]
+                    .iter()
+                    .map(|&i| object.amplitudes()[i])
+                    .sum::<f64>()
+                    / 3.0;
+
+                let depth = centroid[2];
+                let slopes_x = (-centroid[0] / depth, (size[0] - centroid[0]) / depth);
+                let slopes_y = (-centroid[1] / depth, (size[1] - centroid[1]) / depth);
+                let max_slope_x = slopes_x.0.abs().max(slopes_x.1.abs());
+                let max_slope_y = slopes_y.0.abs().max(slopes_y.1.abs());
+
+                Some(Triangle {
+                    origin: vertices[0],
+                    edges,
+                    weight: amplitude * intensity.sqrt() * jacobian,
+                    phase: object.phases()[a],
+                    carrier,
+                    bins_x: bins(slopes_x, max_slope_y, wavelength, width, pitch),
+                    bins_y: bins(slopes_y, max_slope_x, wavelength, height, pitch),
+                    slopes_x,
+                    slopes_y,
+                })
+            })
+            .collect()
+    }
+}
+
+impl CghProcessor for CghProcessorPolygon {
+    fn field(
+        &self,
+        tri_func: &(dyn TriFunc + Sync),
+        config: &CghConfig,
+        object: &Object3d,
+    ) -> Array2<Complex<f64>> {
+        let (width, height) = (config.image_width as usize, config.image_height as usize);
+        let df_x = 1.0 / (width as f64 * config.pixel_pitch);
+        let df_y = 1.0 / (height as f64 * config.pixel_pitch);
+        let k2 = config.wavelength.powi(-2);
+        let triangles = self.triangles(config, object);
+
+        let mut spectrum = Array2::<Complex<f64>>::zeros((height, width));
+        spectrum
+            .axis_iter_mut(Axis(0))
+            .into_par_iter()
+            .enumerate()
+            .for_each(|(ky, mut row)| {
+                let bin_y = fft::frequency_index(ky, height) as isize;
+                let fy = bin_y as f64 * df_y;
+
+                for triangle in &triangles {
+                    if bin_y < triangle.bins_y.0 || bin_y > triangle.bins_y.1 {
+                        continue;
+                    }
+
+                    for bin_x in triangle.bins_x.0..=triangle.bins_x.1 {
+                        let fx = bin_x as f64 * df_x;
+                        let w2 = k2 - fx.powi(2) - fy.powi(2);
+                        if w2 <= 0.0 {
+                            continue;
+                        }
+                        let w = w2.sqrt();
+                        let (slope_x, slope_y) = (fx / w, fy / w);
+                        if slope_x < triangle.slopes_x.0
+                            || slope_x > triangle.slopes_x.1
+                            || slope_y < triangle.slopes_y.0
+                            || slope_y > triangle.slopes_y.1
+                        {
+                            continue;
+                        }
+
+                        let kx = bin_x.rem_euclid(width as isize) as usize;
+                        row[kx] += triangle.spectrum(tri_func, [fx, fy, -w]);
+                    }
+                }
+            });
+
+        fft::fft2(&mut spectrum, Direction::Inverse);
+        // Undo the 1/N of the inverse FFT and apply the frequency step, so that
+        // the sum approximates the continuous inverse transform.
+        spectrum *= Complex::from((width * height) as f64 * df_x * df_y);
+
+        spectrum
+    }
+}
+
+impl Triangle {
+    /// Spectrum at the frequency `q = (fx, fy, -w)`, whose plane wave reaches
+    /// the hologram from a source at positive depth.
+    #[inline]
+    fn spectrum(&self, tri_func: &(dyn TriFunc + Sync), q: [f64; 3]) -> Complex<f64> {
+        let q = sub(q, self.carrier);
+        let alpha = dot(q, self.edges[0]);
+        let beta = dot(q, self.edges[1]);
+
+        let cycles = (self.phase / (2.0 * PI) - dot(q, self.origin)).rem_euclid(1.0);
+        let (sin, cos) = tri_func.sincos(cycles);
+        let shift = Complex::new(cos, sin);
+
+        self.weight * shift * reference_spectrum(alpha, beta)
+    }
+}
+
+/// Fourier transform `∫∫ exp(-2πi (αs + βt)) dt ds` of the reference
+/// triangle `0 ≤ t ≤ s ≤ 1`.
+#[inline]
+fn reference_spectrum(alpha: f64, beta: f64) -> Complex<f64> {
+    const EPSILON: f64 = 1e-6;
+
+    if beta.abs() > EPSILON {
+        (edge_spectrum(alpha) - edge_spectrum(alpha + beta)) / Complex::new(0.0, 2.0 * PI * beta)
+    } else if alpha.abs() > EPSILON {
+        // ∫ s exp(-2πi αs) ds
+        let c = Complex::new(0.0, -2.0 * PI * alpha);
+        let e = c.exp();
+        e / c - (e - 1.0) / (c * c)
+    } else {
+        Complex::new(0.5, 0.0)
+    }
+}
+
+/// `∫ exp(-2πi γs) ds` over `0..1`, i.e. `exp(-πiγ) sinc(γ)`.
+#[inline]
+fn edge_spectrum(gamma: f64) -> Complex<f64> {
+    let sinc = match gamma == 0.0 {
+        true => 1.0,
+        false => (PI * gamma).sin() / (PI * gamma),
+    };
+    Complex::from_polar(sinc, -PI * gamma)
+}
+
+/// Signed FFT bins of the spatial frequencies whose plane waves have slopes
+/// `fx / w` within `slopes`, for any perpendicular slope up to `other`.
+fn bins(slopes: (f64, f64), other: f64, wavelength: f64, len: usize, pitch: f64) -> (isize, isize) {
+    let frequency = |slope: f64, outward: bool| {
+        let other = if outward { 0.0 } else { other };
+        slope / (wavelength * (1.0 + slope.powi(2) + other.powi(2)).sqrt())
+    };
+    let lower = frequency(slopes.0, slopes.0 < 0.0);
+    let upper = frequency(slopes.1, slopes.1 > 0.0);
+
+    let df = 1.0 / (len as f64 * pitch);
+    let first = -((len / 2) as isize);
+    let last = len.div_ceil(2) as isize - 1;
+
+    (
+        ((lower / df).ceil() as isize).max(first),
+        ((upper / df).floor() as isize).min(last),
+    )
+}
+
+#[inline]
+fn sub(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+#[inline]
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+#[inline]
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+#[inline]
+fn norm(a: [f64; 3]) -> f64 {
+    dot(a, a).sqrt()
+}
+
+#[inline]
+fn normalize(a: [f64; 3]) -> [f64; 3] {
+    let length = norm(a);
+    a.map(|v| v / length)
+}
@@ -1,47 +1,49 @@
 use ndarray::parallel::prelude::*;
 use ndarray::Array2;
 use num::{Complex, Zero};
+use std::f64::consts::PI;
 
 use super::{CghConfig, CghProcessor};
 
 use crate::lut::TriFunc;
 use crate::object::Object3d;
 
+/// Computes row `$y` pixel by pixel, advancing the phase of every point by
+/// the recurrence and evaluating the phases of all the points with one
+/// `TriFunc::eval_batch` call per pixel.
 macro_rules! process {
     ($y:expr, $row:expr, $tri_func:expr, $config:expr, $object:expr) => {{
-        let mut theta_prev = vec![0.0; $object.points().len()];
-        let mut delta_prev = vec![0.0; $object.points().len()];
+        let mut theta = vec![0.0; $object.points().len()];
+        let mut delta = vec![0.0; $object.points().len()];
         let mut zeta = vec![0.0; $object.points().len()];
+        let mut wave = vec![Complex::<f64>::zero(); $object.points().len()];
 
-        let mut complex0 = Complex::<f64>::zero();
         for (j, &point) in $object.points().iter().enumerate() {
             let point = (point * $config.scalar) + $config.offset;
-            theta_prev[j] = ($config.pixel_pitch / $config.wavelength)
-                * (point.z + (point.x.powi(2) + ($y as f64 - point.y).powi(2)) / (2.0 * point.z));
-            delta_prev[j] = ($config.pixel_pitch / (2.0 * $config.wavelength * point.z))
+            theta[j] = ($config.pixel_pitch / $config.wavelength)
+                * (point.z + (point.x.powi(2) + ($y as f64 - point.y).powi(2)) / (2.0 * point.z))
+                + $object.phases()[j] / (2.0 * PI);
+            delta[j] = ($config.pixel_pitch / (2.0 * $config.wavelength * point.z))
                 * (2.0 * -point.x + 1.0);
             zeta[j] = $config.pixel_pitch / ($config.wavelength * point.z);
-            complex0 += Complex::new($tri_func.cos(theta_prev[j]), $tri_func.sin(theta_prev[j]));
         }
 
-        let mut iter = $row.iter_mut();
-
-        *iter.next().unwrap() = f64::atan2(complex0.im, complex0.re);
-
-        for pixel in iter {
-            let mut complex = Complex::<f64>::zero();
-
-            for (j, _) in $object.points().iter().enumerate() {
-                let theta = theta_prev[j] + delta_prev[j];
-                let delta = delta_prev[j] + zeta[j];
-
-                complex += Complex::new($tri_func.cos(theta), $tri_func.sin(theta));
-
-                theta_prev[j] = theta;
-                delta_prev[j] = delta;
+        for (x, pixel) in $row.iter_mut().enumerate() {
+            if x > 0 {
+                for ((theta, delta), &zeta) in theta.iter_mut().zip(&mut delta).zip(&zeta) {
+                    *theta += *delta;
+                    *delta += zeta;
+                }
             }
 
-            *pixel = f64::atan2(complex.im, complex.re);
+            $tri_func.eval_batch(&theta, &mut wave);
+            *pixel = $object
+                .amplitudes()
+                .iter()
+                .zip(&wave)
+                .fold(Complex::zero(), |complex, (&amplitude, &wave)| {
+                    complex + amplitude * wave
+                });
         }
     }};
 }
@@ -49,14 +51,16 @@ macro_rules! process {
 pub struct CghProcessorRecurrence;
 
 impl CghProcessor for CghProcessorRecurrence {
-    fn process(
+    fn field(
         &self,
         tri_func: &(dyn TriFunc + Sync),
         config: &CghConfig,
         object: &Object3d,
-    ) -> Array2<f64> {
-        let mut array =
-            Array2::<f64>::zeros((config.image_height as usize, config.image_width as usize));
+    ) -> Array2<Complex<f64>> {
+        let mut array = Array2::<Complex<f64>>::zeros((
+            config.image_height as usize,
+            config.image_width as usize,
+        ));
 
         array
             .outer_iter_mut()
@@ -70,14 +74,16 @@ impl CghProcessor for CghProcessorRecurrence {
 pub struct CghProcessorRecurrenceParallel;
 
 impl CghProcessor for CghProcessorRecurrenceParallel {
-    fn process(
+    fn field(
         &self,
         tri_func: &(dyn TriFunc + Sync),
         config: &CghConfig,
         object: &Object3d,
-    ) -> Array2<f64> {
-        let mut array =
-            Array2::<f64>::zeros((config.image_height as usize, config.image_width as usize));
+    ) -> Array2<Complex<f64>> {
+        let mut array = Array2::<Complex<f64>>::zeros((
+            config.image_height as usize,
+            config.image_width as usize,
+        ));
 
         array
             .outer_iter_mut()
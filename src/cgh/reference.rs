@@ -0,0 +1,99 @@
+use ndarray::{Array2, Zip};
+use num::Complex;
+use serde::{Deserialize, Serialize};
+
+use super::CghConfig;
+
+use crate::lut::TriFunc;
+use crate::point::Point;
+
+/// Shape of the reference wave on the hologram plane.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(
+    tag = "type",
+    rename_all = "lowercase",
+    rename_all_fields = "kebab-case"
+)]
+pub enum ReferenceWave {
+    /// Plane wave whose direction is tilted by `angle_x` and `angle_y`
+    /// radians from the optical axis towards +x and +y.
+    Plane { angle_x: f64, angle_y: f64 },
+    /// Spherical wave diverging from `source`, given in the same pixel units
+    /// as the object points after `CghConfig::offset` is applied. Its
+    /// amplitude is taken to be uniform over the hologram.
+    Spherical { source: Point },
+}
+
+/// What the hologram records of the interference between the object wave
+/// `O` and the reference wave `R`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Interference {
+    /// The full intensity `|O + R|²`, including the zero order.
+    Intensity,
+    /// Only the interference term `2Re(O R*)`, which is zero-mean.
+    Bipolar,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Reference {
+    pub wave: ReferenceWave,
+    /// Reference amplitude relative to the largest object amplitude on the
+    /// hologram.
+    pub amplitude: f64,
+    pub mode: Interference,
+}
+
+impl Reference {
+    /// Reference field on the hologram, with unit amplitude.
+    pub fn field(
+        &self,
+        tri_func: &(dyn TriFunc + Sync),
+        config: &CghConfig,
+    ) -> Array2<Complex<f64>> {
+        let shape = (config.image_height as usize, config.image_width as usize);
+        let mut field = Array2::zeros(shape);
+        let mut theta = vec![0.0; shape.1];
+
+        for (y, mut row) in field.outer_iter_mut().enumerate() {
+            for (x, theta) in theta.iter_mut().enumerate() {
+                let (x, y) = (x as f64, y as f64);
+                let path = match self.wave {
+                    ReferenceWave::Plane { angle_x, angle_y } => {
+                        x * angle_x.sin() + y * angle_y.sin()
+                    }
+                    ReferenceWave::Spherical { source } => source.distance(&Point::new(x, y, 0.0)),
+                };
+                *theta = (config.pixel_pitch * path / config.wavelength).rem_euclid(1.0);
+            }
+
+            let row = row
+                .as_slice_mut()
+                .expect("rows of a new array are contiguous");
+            tri_func.eval_batch(&theta, row);
+        }
+
+        field
+    }
+
+    /// Real-valued hologram recorded from the object field `object`.
+    pub fn interfere(
+        &self,
+        object: &Array2<Complex<f64>>,
+        tri_func: &(dyn TriFunc + Sync),
+        config: &CghConfig,
+    ) -> Array2<f64> {
+        let max = object.iter().map(|o| o.norm()).fold(0.0, f64::max);
+        let amplitude = self.amplitude * max;
+        let reference = self.field(tri_func, config);
+
+        Zip::from(object).and(&reference).map_collect(|&o, &r| {
+            let r = r * amplitude;
+            match self.mode {
+                Interference::Intensity => (o + r).norm_sqr(),
+                Interference::Bipolar => 2.0 * (o * r.conj()).re,
+            }
+        })
+    }
+}
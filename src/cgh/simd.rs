@@ -0,0 +1,346 @@
+use ndarray::parallel::prelude::*;
+use ndarray::{Array2, ArrayViewMut1};
+use num::Complex;
+use std::f64::consts::PI;
+
+use super::{CghConfig, CghProcessor};
+
+use crate::lut::TriFunc;
+use crate::object::Object3d;
+
+/// Points per vector: the `f64` lanes of an AVX register.
+const LANES: usize = 4;
+
+/// Coefficients of `p` and `q` in `sin(x) = x + x³ p(x²)` and
+/// `cos(x) = 1 - x² / 2 + x⁴ q(x²)` for `|x| <= π/4`, from Cephes, highest
+/// power first.
+const SIN: [f64; 6] = [
+    1.5896230157654656e-10,
+    -2.5050747762857807e-8,
+    2.7557313621385722e-6,
+    -1.984126982958954e-4,
+    8.333333333322118e-3,
+    -1.666666666666663e-1,
+];
+const COS: [f64; 6] = [
+    -1.1358536521387682e-11,
+    2.087570084197473e-9,
+    -2.755731417929674e-7,
+    2.4801587288851704e-5,
+    -1.3888888888873056e-3,
+    4.1666666666666595e-2,
+];
+
+/// Instruction set used by the SIMD processors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kernel {
+    /// Portable code over arrays of `LANES` values, in the same order as the
+    /// vector kernels.
+    Scalar,
+    /// AVX2 and FMA on x86-64, four `f64` per instruction.
+    Avx2,
+}
+
+impl Kernel {
+    /// The fastest kernel the running CPU supports.
+    pub fn detect() -> Self {
+        match Kernel::Avx2.is_supported() {
+            true => Kernel::Avx2,
+            false => Kernel::Scalar,
+        }
+    }
+
+    pub fn is_supported(self) -> bool {
+        match self {
+            Kernel::Scalar => true,
+            #[cfg(target_arch = "x86_64")]
+            Kernel::Avx2 => is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma"),
+            #[cfg(not(target_arch = "x86_64"))]
+            Kernel::Avx2 => false,
+        }
+    }
+}
+
+impl Default for Kernel {
+    fn default() -> Self {
+        Self::detect()
+    }
+}
+
+/// Object points in structure-of-arrays form, so that the kernels load
+/// `LANES` consecutive points with one instruction each. Padded to a
+/// multiple of `LANES` with silent points (of zero amplitude).
+struct Points {
+    x: Vec<f64>,
+    y: Vec<f64>,
+    z: Vec<f64>,
+    amplitude: Vec<f64>,
+    /// In cycles.
+    phase: Vec<f64>,
+}
+
+impl Points {
+    fn new(config: &CghConfig, object: &Object3d) -> Self {
+        let len = object.size().next_multiple_of(LANES);
+        let mut points = Points {
+            x: Vec::with_capacity(len),
+            y: Vec::with_capacity(len),
+            z: Vec::with_capacity(len),
+            amplitude: Vec::with_capacity(len),
+            phase: Vec::with_capacity(len),
+        };
+
+        let amplitudes = object.points().iter().zip(object.amplitudes());
+        for ((&point, &amplitude), &phase) in amplitudes.zip(object.phases()) {
+            let point = (point * config.scalar) + config.offset;
+            points.x.push(point.x);
+            points.y.push(point.y);
+            points.z.push(point.z);
+            points.amplitude.push(amplitude);
+            points.phase.push(phase / (2.0 * PI));
+        }
+        for _ in object.size()..len {
+            points.x.push(0.0);
+            points.y.push(0.0);
+            points.z.push(1.0);
+            points.amplitude.push(0.0);
+            points.phase.push(0.0);
+        }
+
+        points
+    }
+}
+
+/// The spherical waves `exp(i(kr + φ)) / r` of the naive processors,
+/// evaluated `LANES` points at a time.
+///
+/// The points are laid out as structures of arrays and the phases go through
+/// a vectorized polynomial sine and cosine, accurate to about `1e-15`, in
+/// place of `tri_func`, which is ignored. The AVX2 kernel is used when the
+/// CPU supports it (see `Kernel::detect`); `f64` is kept throughout, as `f32`
+/// cannot hold phases of millions of cycles.
+#[derive(Default)]
+pub struct CghProcessorSimd {
+    pub kernel: Kernel,
+}
+
+impl CghProcessor for CghProcessorSimd {
+    fn field(
+        &self,
+        _tri_func: &(dyn TriFunc + Sync),
+        config: &CghConfig,
+        object: &Object3d,
+    ) -> Array2<Complex<f64>> {
+        let points = Points::new(config, object);
+        let mut array = zeros(self.kernel, config);
+
+        array
+            .outer_iter_mut()
+            .enumerate()
+            .for_each(|(y, row)| process_row(self.kernel, y, row, config, &points));
+
+        array
+    }
+}
+
+/// `CghProcessorSimd` with the rows spread over the rayon thread pool.
+#[derive(Default)]
+pub struct CghProcessorSimdParallel {
+    pub kernel: Kernel,
+}
+
+impl CghProcessor for CghProcessorSimdParallel {
+    fn field(
+        &self,
+        _tri_func: &(dyn TriFunc + Sync),
+        config: &CghConfig,
+        object: &Object3d,
+    ) -> Array2<Complex<f64>> {
+        let points = Points::new(config, object);
+        let mut array = zeros(self.kernel, config);
+
+        array
+            .outer_iter_mut()
+            .into_par_iter()
+            .enumerate()
+            .for_each(|(y, row)| process_row(self.kernel, y, row, config, &points));
+
+        array
+    }
+}
+
+/// The field to fill, once `kernel` is known to be supported.
+fn zeros(kernel: Kernel, config: &CghConfig) -> Array2<Complex<f64>> {
+    assert!(
+        kernel.is_supported(),
+        "{:?} is not supported by this CPU",
+        kernel
+    );
+
+    Array2::zeros((config.image_height as usize, config.image_width as usize))
+}
+
+fn process_row(
+    kernel: Kernel,
+    y: usize,
+    mut row: ArrayViewMut1<Complex<f64>>,
+    config: &CghConfig,
+    points: &Points,
+) {
+    // Squared distance to the row, but for the offset along x.
+    let distance2: Vec<_> = points
+        .y
+        .iter()
+        .zip(&points.z)
+        .map(|(&py, &pz)| (y as f64 - py).powi(2) + pz.powi(2))
+        .collect();
+    let cycles_per_pixel = config.pixel_pitch / config.wavelength;
+
+    for (x, pixel) in row.iter_mut().enumerate() {
+        let pixel_x = x as f64;
+        *pixel = match kernel {
+            Kernel::Scalar => scalar::pixel(pixel_x, &distance2, cycles_per_pixel, points),
+            #[cfg(target_arch = "x86_64")]
+            // Safety: `zeros` checked that the CPU supports it.
+            Kernel::Avx2 => unsafe { avx2::pixel(pixel_x, &distance2, cycles_per_pixel, points) },
+            #[cfg(not(target_arch = "x86_64"))]
+            Kernel::Avx2 => unreachable!(),
+        };
+    }
+}
+
+mod scalar {
+    use num::Complex;
+    use std::f64::consts::PI;
+
+    use super::{Points, COS, LANES, SIN};
+
+    pub(super) fn pixel(
+        pixel_x: f64,
+        distance2: &[f64],
+        cycles_per_pixel: f64,
+        points: &Points,
+    ) -> Complex<f64> {
+        let mut re = [0.0; LANES];
+        let mut im = [0.0; LANES];
+
+        for j in (0..distance2.len()).step_by(LANES) {
+            for lane in 0..LANES {
+                let i = j + lane;
+                let r = ((pixel_x - points.x[i]).powi(2) + distance2[i]).sqrt();
+                let (sin, cos) = sincos(cycles_per_pixel * r + points.phase[i]);
+                let weight = points.amplitude[i] / r;
+                re[lane] += weight * cos;
+                im[lane] += weight * sin;
+            }
+        }
+
+        Complex::new(re.iter().sum(), im.iter().sum())
+    }
+
+    /// Sine and cosine of `cycles` full turns, by the same steps as the
+    /// vector kernels.
+    #[inline]
+    pub(super) fn sincos(cycles: f64) -> (f64, f64) {
+        // `turn` in -1/2..=1/2, then `rest` in -1/8..=1/8 of a turn away from
+        // the nearest of the quarter turns `quarter`.
+        let turn = cycles - cycles.round_ties_even();
+        let quarter = (4.0 * turn).round_ties_even();
+        let x = 2.0 * PI * (turn - quarter / 4.0);
+        let x2 = x * x;
+
+        let sin = x + x * x2 * SIN.iter().fold(0.0, |sum, &c| sum * x2 + c);
+        let cos = 1.0 - x2 / 2.0 + x2 * x2 * COS.iter().fold(0.0, |sum, &c| sum * x2 + c);
+
+        match quarter.rem_euclid(4.0) as u8 {
+            0 => (sin, cos),
+            1 => (cos, -sin),
+            2 => (-sin, -cos),
+            _ => (-cos, sin),
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod avx2 {
+    use num::Complex;
+    use std::arch::x86_64::*;
+    use std::f64::consts::PI;
+
+    use super::{Points, COS, LANES, SIN};
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2 and FMA.
+    #[target_feature(enable = "avx2,fma")]
+    pub(super) unsafe fn pixel(
+        pixel_x: f64,
+        distance2: &[f64],
+        cycles_per_pixel: f64,
+        points: &Points,
+    ) -> Complex<f64> {
+        let pixel_x = _mm256_set1_pd(pixel_x);
+        let cycles_per_pixel = _mm256_set1_pd(cycles_per_pixel);
+        let mut re = _mm256_setzero_pd();
+        let mut im = _mm256_setzero_pd();
+
+        for j in (0..distance2.len()).step_by(LANES) {
+            let dx = _mm256_sub_pd(pixel_x, _mm256_loadu_pd(points.x.as_ptr().add(j)));
+            let r = _mm256_sqrt_pd(_mm256_fmadd_pd(
+                dx,
+                dx,
+                _mm256_loadu_pd(distance2.as_ptr().add(j)),
+            ));
+            let phase = _mm256_loadu_pd(points.phase.as_ptr().add(j));
+            let (sin, cos) = sincos(_mm256_fmadd_pd(cycles_per_pixel, r, phase));
+            let weight = _mm256_div_pd(_mm256_loadu_pd(points.amplitude.as_ptr().add(j)), r);
+            re = _mm256_fmadd_pd(weight, cos, re);
+            im = _mm256_fmadd_pd(weight, sin, im);
+        }
+
+        let mut lanes = [[0.0; LANES]; 2];
+        _mm256_storeu_pd(lanes[0].as_mut_ptr(), re);
+        _mm256_storeu_pd(lanes[1].as_mut_ptr(), im);
+        Complex::new(lanes[0].iter().sum(), lanes[1].iter().sum())
+    }
+
+    /// `scalar::sincos` on four lanes.
+    #[inline]
+    #[target_feature(enable = "avx2,fma")]
+    unsafe fn sincos(cycles: __m256d) -> (__m256d, __m256d) {
+        const NEAREST: i32 = _MM_FROUND_TO_NEAREST_INT | _MM_FROUND_NO_EXC;
+        let turn = _mm256_sub_pd(cycles, _mm256_round_pd::<NEAREST>(cycles));
+        let quarter = _mm256_round_pd::<NEAREST>(_mm256_mul_pd(_mm256_set1_pd(4.0), turn));
+        let rest = _mm256_fnmadd_pd(quarter, _mm256_set1_pd(0.25), turn);
+        let x = _mm256_mul_pd(_mm256_set1_pd(2.0 * PI), rest);
+        let x2 = _mm256_mul_pd(x, x);
+
+        let polynomial = |coefficients: [f64; 6]| {
+            coefficients.iter().fold(_mm256_setzero_pd(), |sum, &c| {
+                _mm256_fmadd_pd(sum, x2, _mm256_set1_pd(c))
+            })
+        };
+        let sin = _mm256_fmadd_pd(_mm256_mul_pd(x, x2), polynomial(SIN), x);
+        let cos = _mm256_fmadd_pd(
+            _mm256_mul_pd(x2, x2),
+            polynomial(COS),
+            _mm256_fnmadd_pd(x2, _mm256_set1_pd(0.5), _mm256_set1_pd(1.0)),
+        );
+
+        // The quarter turn modulo 4, as in `scalar::sincos`: odd ones swap
+        // sine and cosine, 2 and 3 negate the sine, 1 and 2 the cosine.
+        let four = _mm256_set1_pd(4.0);
+        let quarter =
+            _mm256_fnmadd_pd(four, _mm256_floor_pd(_mm256_div_pd(quarter, four)), quarter);
+        let equals = |value: f64| _mm256_cmp_pd::<_CMP_EQ_OQ>(quarter, _mm256_set1_pd(value));
+        let odd = _mm256_or_pd(equals(1.0), equals(3.0));
+        let sign = _mm256_set1_pd(-0.0);
+        let negate_sin = _mm256_and_pd(_mm256_or_pd(equals(2.0), equals(3.0)), sign);
+        let negate_cos = _mm256_and_pd(_mm256_or_pd(equals(1.0), equals(2.0)), sign);
+
+        (
+            _mm256_xor_pd(_mm256_blendv_pd(sin, cos, odd), negate_sin),
+            _mm256_xor_pd(_mm256_blendv_pd(cos, sin, odd), negate_cos),
+        )
+    }
+}
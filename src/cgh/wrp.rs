@@ -0,0 +1,133 @@
+use ndarray::parallel::prelude::*;
+use ndarray::{s, Array2, Axis};
+use num::Complex;
+use std::f64::consts::PI;
+
+use super::{CghConfig, CghProcessor};
+
+use crate::lut::TriFunc;
+use crate::object::Object3d;
+use crate::propagation::Propagator;
+
+const TAPER_START: f64 = 0.5;
+
+/// Wavefront recording plane (WRP) method.
+///
+/// The field of each point is first accumulated on a virtual plane placed
+/// `distance` in front of the nearest point, only within the small (tapered)
+/// window its light can reach at the maximum diffraction angle `asin(λ / 2p)`.
+/// The WRP is then propagated to the hologram with the band-limited angular
+/// spectrum method on a twice zero-padded grid.
+pub struct CghProcessorWrp {
+    /// Distance between the WRP and the nearest object point, in the same
+    /// pixel units as `CghConfig::offset`.
+    pub distance: f64,
+}
+
+impl Default for CghProcessorWrp {
+    fn default() -> Self {
+        CghProcessorWrp { distance: 256.0 }
+    }
+}
+
+impl CghProcessorWrp {
+    /// Returns the WRP field and its depth (in pixel units).
+    fn record(
+        &self,
+        tri_func: &(dyn TriFunc + Sync),
+        config: &CghConfig,
+        object: &Object3d,
+    ) -> (Array2<Complex<f64>>, f64) {
+        let width = config.image_width as usize;
+        let height = config.image_height as usize;
+
+        let points: Vec<_> = object
+            .points()
+            .iter()
+            .map(|&point| (point * config.scalar) + config.offset)
+            .collect();
+        let nearest = points.iter().map(|p| p.z).fold(f64::INFINITY, f64::min);
+        let wrp_z = match nearest.is_finite() {
+            true => nearest - self.distance,
+            false => 0.0,
+        };
+
+        let sin_max = (config.wavelength / (2.0 * config.pixel_pitch)).min(1.0);
+        let tan_max = sin_max / (1.0 - sin_max.powi(2)).sqrt();
+
+        let mut wrp = Array2::<Complex<f64>>::zeros((height, width));
+
+        wrp.axis_iter_mut(Axis(0))
+            .into_par_iter()
+            .enumerate()
+            .for_each(|(v, mut row)| {
+                let v = v as f64;
+                let mut theta = Vec::with_capacity(width);
+                let mut weight = Vec::with_capacity(width);
+                let mut wave = vec![Complex::default(); width];
+                let points = points.iter().zip(object.amplitudes());
+
+                for ((point, &amplitude), &phase) in points.zip(object.phases()) {
+                    let dz = point.z - wrp_z;
+                    let radius = dz * tan_max;
+                    let dy = v - point.y;
+                    if dy.abs() > radius {
+                        continue;
+                    }
+
+                    let half = (radius.powi(2) - dy.powi(2)).sqrt();
+                    let start = (point.x - half).ceil().max(0.0) as usize;
+                    let end = ((point.x + half).floor() + 1.0).clamp(0.0, width as f64) as usize;
+
+                    theta.clear();
+                    weight.clear();
+                    for u in start..end {
+                        let dx = u as f64 - point.x;
+                        let taper = taper((dx.powi(2) + dy.powi(2)).sqrt() / radius);
+                        let distance = (dx.powi(2) + dy.powi(2) + dz.powi(2)).sqrt();
+                        theta.push(
+                            config.pixel_pitch * distance / config.wavelength + phase / (2.0 * PI),
+                        );
+                        weight.push(taper * amplitude / distance);
+                    }
+
+                    let wave = &mut wave[..theta.len()];
+                    tri_func.eval_batch(&theta, wave);
+                    let pixels = row.slice_mut(s![start..end]);
+                    for ((pixel, &weight), &wave) in pixels.into_iter().zip(&weight).zip(&*wave) {
+                        *pixel += weight * wave;
+                    }
+                }
+            });
+
+        (wrp, wrp_z)
+    }
+}
+
+/// Smooth window over the normalized radius `rho` (0 at the center, 1 at the
+/// edge). A hard cut-off of the spherical wave adds an edge-diffraction term as
+/// strong as the wave itself, so the outer half of the window is tapered.
+#[inline]
+fn taper(rho: f64) -> f64 {
+    if rho <= TAPER_START {
+        1.0
+    } else if rho >= 1.0 {
+        0.0
+    } else {
+        0.5 * (1.0 + (PI * (rho - TAPER_START) / (1.0 - TAPER_START)).cos())
+    }
+}
+
+impl CghProcessor for CghProcessorWrp {
+    fn field(
+        &self,
+        tri_func: &(dyn TriFunc + Sync),
+        config: &CghConfig,
+        object: &Object3d,
+    ) -> Array2<Complex<f64>> {
+        let (wrp, wrp_z) = self.record(tri_func, config, object);
+        let propagator = Propagator::new(config.pixel_pitch, config.wavelength);
+
+        propagator.angular_spectrum(&wrp, wrp_z * config.pixel_pitch)
+    }
+}
@@ -0,0 +1,89 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    /// Wraps an error raised while reading or writing the file at `path`.
+    File {
+        path: PathBuf,
+        source: Box<Error>,
+    },
+    /// The stream ended inside the record of point `index` (0-based).
+    TruncatedPoint {
+        index: usize,
+        count: usize,
+    },
+    /// The header announces more points than the stream can hold.
+    ImplausiblePointCount {
+        count: u64,
+        stream_len: u64,
+    },
+    InvalidObject(String),
+    InvalidImage(String),
+    UnsupportedFormat(String),
+    /// A job file that cannot be parsed or fails validation.
+    InvalidJob(String),
+    /// A benchmark matrix that cannot be run.
+    InvalidBench(String),
+    /// The image dimensions do not fit the pixel buffer or file format.
+    DimensionOverflow {
+        width: u32,
+        height: u32,
+    },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    pub(crate) fn with_path<P: Into<PathBuf>>(self, path: P) -> Self {
+        Error::File {
+            path: path.into(),
+            source: Box::new(self),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(error) => write!(f, "{}", error),
+            Error::File { path, source } => write!(f, "{}: {}", path.display(), source),
+            Error::TruncatedPoint { index, count } => write!(
+                f,
+                "truncated point record at index {} (expected {} points)",
+                index, count
+            ),
+            Error::ImplausiblePointCount { count, stream_len } => write!(
+                f,
+                "implausible point count {} for a stream of {} bytes",
+                count, stream_len
+            ),
+            Error::InvalidObject(message) => write!(f, "invalid object: {}", message),
+            Error::InvalidImage(message) => write!(f, "invalid image: {}", message),
+            Error::UnsupportedFormat(message) => write!(f, "unsupported format: {}", message),
+            Error::InvalidJob(message) => write!(f, "invalid job: {}", message),
+            Error::InvalidBench(message) => write!(f, "invalid benchmark: {}", message),
+            Error::DimensionOverflow { width, height } => {
+                write!(f, "image dimensions {}x{} are too large", width, height)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(error) => Some(error),
+            Error::File { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io(error)
+    }
+}
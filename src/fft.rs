@@ -0,0 +1,206 @@
+//! A self-contained FFT for complex `f64` data of any length: iterative
+//! radix-2 for powers of two and Bluestein's algorithm otherwise. Two
+//! dimensional transforms run their rows and columns on rayon.
+
+use ndarray::parallel::prelude::*;
+use ndarray::{Array2, Axis};
+use num::{Complex, Zero};
+use std::f64::consts::PI;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// `X[k] = Σ x[n] exp(-2πi kn / N)`
+    Forward,
+    /// `x[n] = (1 / N) Σ X[k] exp(2πi kn / N)`
+    Inverse,
+}
+
+#[derive(Debug, Clone)]
+enum Algorithm {
+    Radix2 {
+        twiddles: Vec<Complex<f64>>,
+    },
+    Bluestein {
+        chirp: Vec<Complex<f64>>,
+        kernel: Vec<Complex<f64>>,
+        inner: Box<Fft>,
+    },
+}
+
+/// A precomputed transform of a fixed length.
+#[derive(Debug, Clone)]
+pub struct Fft {
+    len: usize,
+    algorithm: Algorithm,
+}
+
+impl Fft {
+    pub fn new(len: usize) -> Self {
+        if len.is_power_of_two() || len == 0 {
+            let twiddles = (0..len / 2)
+                .map(|k| Complex::from_polar(1.0, -2.0 * PI * k as f64 / len as f64))
+                .collect();
+            return Fft {
+                len,
+                algorithm: Algorithm::Radix2 { twiddles },
+            };
+        }
+
+        // Bluestein: express the DFT as a convolution with a chirp, evaluated
+        // with a power-of-two transform of at least 2N - 1 points.
+        let m = (2 * len - 1).next_power_of_two();
+        let inner = Fft::new(m);
+
+        let chirp: Vec<_> = (0..len)
+            .map(|n| {
+                // n² mod 2N keeps the angle argument small and exact.
+                let n2 = (n as u128 * n as u128 % (2 * len as u128)) as f64;
+                Complex::from_polar(1.0, -PI * n2 / len as f64)
+            })
+            .collect();
+
+        let mut kernel = vec![Complex::zero(); m];
+        kernel[0] = chirp[0].conj();
+        for n in 1..len {
+            kernel[n] = chirp[n].conj();
+            kernel[m - n] = chirp[n].conj();
+        }
+        inner.process(&mut kernel, Direction::Forward);
+
+        Fft {
+            len,
+            algorithm: Algorithm::Bluestein {
+                chirp,
+                kernel,
+                inner: Box::new(inner),
+            },
+        }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Transforms `data` in place. Its length must equal `self.len()`.
+    pub fn process(&self, data: &mut [Complex<f64>], direction: Direction) {
+        assert_eq!(data.len(), self.len, "FFT length mismatch");
+
+        match &self.algorithm {
+            Algorithm::Radix2 { twiddles } => radix2(data, twiddles, direction),
+            Algorithm::Bluestein {
+                chirp,
+                kernel,
+                inner,
+            } => {
+                let m = inner.len();
+                let mut buffer = vec![Complex::zero(); m];
+
+                // The inverse transform is the forward transform of the
+                // conjugated input, conjugated back.
+                let conjugate = direction == Direction::Inverse;
+                for ((b, &x), &c) in buffer.iter_mut().zip(data.iter()).zip(chirp) {
+                    *b = if conjugate { x.conj() } else { x } * c;
+                }
+
+                inner.process(&mut buffer, Direction::Forward);
+                buffer.iter_mut().zip(kernel).for_each(|(b, &k)| *b *= k);
+                inner.process(&mut buffer, Direction::Inverse);
+
+                for ((x, &b), &c) in data.iter_mut().zip(&buffer).zip(chirp) {
+                    let value = b * c;
+                    *x = if conjugate {
+                        value.conj() / self.len as f64
+                    } else {
+                        value
+                    };
+                }
+            }
+        }
+    }
+}
+
+fn radix2(data: &mut [Complex<f64>], twiddles: &[Complex<f64>], direction: Direction) {
+    let n = data.len();
+    if n <= 1 {
+        return;
+    }
+
+    let bits = n.trailing_zeros();
+    for i in 0..n {
+        let j = i.reverse_bits() >> (usize::BITS - bits);
+        if i < j {
+            data.swap(i, j);
+        }
+    }
+
+    let mut size = 2;
+    while size <= n {
+        let half = size / 2;
+        let step = n / size;
+        for start in (0..n).step_by(size) {
+            for k in 0..half {
+                let twiddle = match direction {
+                    Direction::Forward => twiddles[k * step],
+                    Direction::Inverse => twiddles[k * step].conj(),
+                };
+                let a = data[start + k];
+                let b = data[start + k + half] * twiddle;
+                data[start + k] = a + b;
+                data[start + k + half] = a - b;
+            }
+        }
+        size *= 2;
+    }
+
+    if direction == Direction::Inverse {
+        let scale = 1.0 / n as f64;
+        data.iter_mut().for_each(|x| *x *= scale);
+    }
+}
+
+/// Transforms every row and then every column of `array` in place.
+pub fn fft2(array: &mut Array2<Complex<f64>>, direction: Direction) {
+    let (height, width) = array.dim();
+    let row_fft = Fft::new(width);
+    let column_fft = Fft::new(height);
+
+    array.axis_iter_mut(Axis(0)).into_par_iter().for_each_init(
+        || vec![Complex::zero(); width],
+        |buffer, mut row| {
+            buffer.iter_mut().zip(row.iter()).for_each(|(b, &x)| *b = x);
+            row_fft.process(buffer, direction);
+            row.iter_mut().zip(buffer.iter()).for_each(|(x, &b)| *x = b);
+        },
+    );
+
+    array.axis_iter_mut(Axis(1)).into_par_iter().for_each_init(
+        || vec![Complex::zero(); height],
+        |buffer, mut column| {
+            buffer
+                .iter_mut()
+                .zip(column.iter())
+                .for_each(|(b, &x)| *b = x);
+            column_fft.process(buffer, direction);
+            column
+                .iter_mut()
+                .zip(buffer.iter())
+                .for_each(|(x, &b)| *x = b);
+        },
+    );
+}
+
+/// Signed frequency index of DFT bin `k` out of `n` (`0, 1, .., -2, -1`).
+#[inline]
+pub fn frequency_index(k: usize, n: usize) -> f64 {
+    if k < n.div_ceil(2) {
+        k as f64
+    } else {
+        k as f64 - n as f64
+    }
+}
@@ -0,0 +1,235 @@
+//! Iterative Fourier transform algorithms (IFTA) for phase-only holograms of
+//! 2D targets.
+//!
+//! The hologram and the image are related by a single Fourier transform, as
+//! for an SLM in the front focal plane of a lens and the image in its back
+//! focal plane. The target is centered in the image plane; the rest of the
+//! plane is the noise region.
+
+use ndarray::{Array2, Zip};
+use num::Complex;
+use std::f64::consts::PI;
+
+use crate::bmp::Image;
+use crate::fft::{fft2, Direction};
+use crate::random::SplitMix64;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Algorithm {
+    /// Gerchberg–Saxton: the image amplitude is replaced by the target
+    /// amplitude everywhere, zero outside the target.
+    GerchbergSaxton,
+    /// Weighted Gerchberg–Saxton: like `GerchbergSaxton`, but the imposed
+    /// amplitude is reweighted each iteration to even out the reconstruction.
+    WeightedGerchbergSaxton,
+    /// Mixed-region amplitude freedom: only the target region is constrained,
+    /// to `mixing` times the target amplitude scaled to the energy of the
+    /// whole image plane, while the noise region keeps `1 - mixing` of its
+    /// field, trading efficiency for uniformity.
+    Mraf { mixing: f64 },
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Ifta {
+    pub algorithm: Algorithm,
+    pub iterations: usize,
+    /// Seed of the random initial phase in the image plane.
+    pub seed: u64,
+}
+
+/// Hologram found by `Ifta::run`.
+#[derive(Debug, Clone)]
+pub struct Retrieval {
+    /// Unit-amplitude hologram field, ready for `encoding::Kinoform`.
+    pub field: Array2<Complex<f64>>,
+    /// Normalized RMS error of the reconstructed intensity within the target
+    /// after each iteration.
+    pub errors: Vec<f64>,
+}
+
+impl Ifta {
+    pub fn new(algorithm: Algorithm) -> Self {
+        Ifta {
+            algorithm,
+            iterations: 50,
+            seed: 0,
+        }
+    }
+
+    /// Computes a `width` × `height` phase hologram reconstructing the
+    /// intensity `target`, which must not be larger than the hologram.
+    pub fn run(&self, target: &Array2<f64>, width: usize, height: usize) -> Retrieval {
+        self.run_with(target, width, height, |_, _| {})
+    }
+
+    /// Like `run`, calling `report(iteration, error)` after each iteration.
+    pub fn run_with<F>(
+        &self,
+        target: &Array2<f64>,
+        width: usize,
+        height: usize,
+        mut report: F,
+    ) -> Retrieval
+    where
+        F: FnMut(usize, f64),
+    {
+        let (target_height, target_width) = target.dim();
+        assert!(
+            target_width <= width && target_height <= height,
+            "target is larger than the hologram"
+        );
+
+        // Target amplitude and region, laid out in FFT order.
+        let top = (height - target_height) / 2;
+        let left = (width - target_width) / 2;
+        let mut amplitude = Array2::<f64>::zeros((height, width));
+        let mut signal = Array2::from_elem((height, width), false);
+        for ((y, x), &intensity) in target.indexed_iter() {
+            let index = fft_index(top + y, left + x, height, width);
+            amplitude[index] = intensity.max(0.0).sqrt();
+            signal[index] = true;
+        }
+
+        let mut random = SplitMix64::new(self.seed);
+        let mut image = amplitude.mapv(|a| Complex::from_polar(a, 2.0 * PI * random.next_f64()));
+        let mut weights = amplitude.clone();
+        let mut hologram = Array2::zeros((height, width));
+        let mut errors = Vec::with_capacity(self.iterations);
+
+        for iteration in 0..self.iterations {
+            hologram.assign(&image);
+            fft2(&mut hologram, Direction::Inverse);
+            hologram.mapv_inplace(unit);
+
+            image.assign(&hologram);
+            fft2(&mut image, Direction::Forward);
+
+            let error = intensity_error(&image, &amplitude, &signal);
+            errors.push(error);
+            report(iteration, error);
+
+            match self.algorithm {
+                Algorithm::GerchbergSaxton => {
+                    Zip::from(&mut image)
+                        .and(&amplitude)
+                        .for_each(|e, &a| *e = a * unit(*e));
+                }
+                Algorithm::WeightedGerchbergSaxton => {
+                    let scale = fit_scale(&image, &amplitude, &signal);
+                    Zip::from(&mut image)
+                        .and(&mut weights)
+                        .and(&amplitude)
+                        .for_each(|e, w, &a| {
+                            let reconstructed = scale * e.norm();
+                            if a > 0.0 && reconstructed > 0.0 {
+                                *w *= a / reconstructed;
+                            }
+                            *e = *w * unit(*e);
+                        });
+                }
+                Algorithm::Mraf { mixing } => {
+                    // The target amplitude, scaled to carry the energy of the
+                    // whole image plane.
+                    let total = image.iter().map(|e| e.norm_sqr()).sum::<f64>();
+                    let target = amplitude.iter().map(|a| a * a).sum::<f64>();
+                    let scale = mixing * (total / target).sqrt();
+                    Zip::from(&mut image).and(&amplitude).and(&signal).for_each(
+                        |e, &a, &inside| {
+                            *e = match inside {
+                                true => scale * a * unit(*e),
+                                false => (1.0 - mixing) * *e,
+                            };
+                        },
+                    );
+                }
+            }
+        }
+
+        Retrieval {
+            field: hologram,
+            errors,
+        }
+    }
+}
+
+/// Target intensity from the gray levels of `image`, scaled to `0..1`.
+pub fn target_from_image(image: &Image) -> Array2<f64> {
+    let (width, height) = (image.width() as usize, image.height() as usize);
+
+    Array2::from_shape_fn((height, width), |(y, x)| {
+        image.get_pixel(x as u32, y as u32) as f64 / 255.0
+    })
+}
+
+/// Index in FFT order (zero frequency first) of the pixel `(y, x)` of a plane
+/// whose center is at `(height / 2, width / 2)`.
+#[inline]
+fn fft_index(y: usize, x: usize, height: usize, width: usize) -> (usize, usize) {
+    (
+        (y + height - height / 2) % height,
+        (x + width - width / 2) % width,
+    )
+}
+
+#[inline]
+fn unit(complex: Complex<f64>) -> Complex<f64> {
+    let norm = complex.norm();
+    match norm > 0.0 {
+        true => complex / norm,
+        false => Complex::new(1.0, 0.0),
+    }
+}
+
+/// Least-squares scale from the reconstructed to the target amplitude.
+fn fit_scale(image: &Array2<Complex<f64>>, amplitude: &Array2<f64>, signal: &Array2<bool>) -> f64 {
+    let (mut cross, mut power) = (0.0, 0.0);
+    Zip::from(image)
+        .and(amplitude)
+        .and(signal)
+        .for_each(|e, &a, &inside| {
+            if inside {
+                cross += e.norm() * a;
+                power += e.norm_sqr();
+            }
+        });
+
+    if power > 0.0 {
+        cross / power
+    } else {
+        0.0
+    }
+}
+
+fn intensity_error(
+    image: &Array2<Complex<f64>>,
+    amplitude: &Array2<f64>,
+    signal: &Array2<bool>,
+) -> f64 {
+    let (mut cross, mut power, mut target_power) = (0.0, 0.0, 0.0);
+    Zip::from(image)
+        .and(amplitude)
+        .and(signal)
+        .for_each(|e, &a, &inside| {
+            if inside {
+                cross += e.norm_sqr() * a.powi(2);
+                power += e.norm_sqr().powi(2);
+                target_power += a.powi(4);
+            }
+        });
+    if power == 0.0 || target_power == 0.0 {
+        return 1.0;
+    }
+
+    let scale = cross / power;
+    let mut error = 0.0;
+    Zip::from(image)
+        .and(amplitude)
+        .and(signal)
+        .for_each(|e, &a, &inside| {
+            if inside {
+                error += (scale * e.norm_sqr() - a.powi(2)).powi(2);
+            }
+        });
+
+    (error / target_power).sqrt()
+}
@@ -0,0 +1,922 @@
+//! Job files: everything needed to compute a hologram (geometry, processor,
+//! trig backend, encoder, input object and output) in one TOML or JSON file,
+//! so that runs can be repeated exactly.
+//!
+//! ```toml
+//! processor = "fresnel-parallel"
+//! encoder = "kinoform"
+//!
+//! [trig]
+//! kind = "lut"
+//! lut-size = 4096
+//! lut-mode = "linear"
+//!
+//! [settings.occlusion]
+//! block-size = 64
+//!
+//! [hologram]
+//! width = 1920
+//! height = 1080
+//! pixel-pitch = 8e-6
+//! wavelength = 520e-9
+//! scale = 40.0
+//!
+//! [input]
+//! path = "data/cube.obj"
+//! sample-spacing = 2.0
+//! transform = { rotate = [0.0, 30.0, 0.0] }
+//!
+//! [output]
+//! path = "out/cube.bmp"
+//! ```
+//!
+//! Everything but `input.path` and `output.path` has a default. Paths are
+//! relative to the working directory. `Job::run` writes the resolved job
+//! (defaults filled in, see `Job::resolved`) next to the hologram as a TOML
+//! sidecar, which can be run again to regenerate it.
+
+use serde::{Deserialize, Serialize};
+use std::ffi::OsStr;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::cgh::color::ColorConfig;
+use crate::cgh::diffusion::{ComplexErrorDiffusion, ErrorDiffusion, Kernel};
+use crate::cgh::encoding::{Amplitude, Binary, DoublePhase, Encoder, Kinoform};
+use crate::cgh::fresnel::{CghProcessorFresnel, CghProcessorFresnelParallel};
+use crate::cgh::layer::{CghProcessorLayer, LayerMethod};
+use crate::cgh::naive::{CghProcessorNaive, CghProcessorNaiveParallel};
+use crate::cgh::occlusion::Occlusion;
+use crate::cgh::polygon::CghProcessorPolygon;
+use crate::cgh::recurrence::{CghProcessorRecurrence, CghProcessorRecurrenceParallel};
+use crate::cgh::reference::{Interference, Reference};
+use crate::cgh::simd::{CghProcessorSimd, CghProcessorSimdParallel};
+use crate::cgh::wrp::CghProcessorWrp;
+use crate::cgh::{generate_cgh_raster, CghConfig, CghProcessor, SampleDepth};
+use crate::error::{Error, Result};
+use crate::lut::{LutMode, TriFunc, TriFuncLut, TriFuncNaive, TrigError};
+use crate::object::{Object3d, ObjectFormat};
+use crate::point::Point;
+use crate::raster::{ImageFormat, Raster};
+use crate::rgbd::RgbdImage;
+
+/// Sizes of `TriFuncLut` that can be selected at run time.
+pub const LUT_SIZES: [usize; 5] = [256, 1024, 4096, 16384, 65536];
+
+/// Parses and prints the `name` of a variant, as used in job files.
+macro_rules! named {
+    ($kind:ident, $what:literal, { $($name:literal => $variant:ident),+ $(,)? }) => {
+        impl FromStr for $kind {
+            type Err = String;
+
+            fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+                match value {
+                    $($name => Ok($kind::$variant),)+
+                    _ => Err(format!(
+                        concat!("unknown ", $what, ", expected one of: {}"),
+                        [$($name),+].join(", ")
+                    )),
+                }
+            }
+        }
+
+        impl fmt::Display for $kind {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                let name = match self {
+                    $($kind::$variant => $name,)+
+                };
+                f.write_str(name)
+            }
+        }
+    };
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ProcessorKind {
+    Naive,
+    NaiveParallel,
+    Fresnel,
+    FresnelParallel,
+    Recurrence,
+    #[default]
+    RecurrenceParallel,
+    Simd,
+    SimdParallel,
+    Wrp,
+    Polygon,
+    Layer,
+}
+
+named!(ProcessorKind, "processor", {
+    "naive" => Naive,
+    "naive-parallel" => NaiveParallel,
+    "fresnel" => Fresnel,
+    "fresnel-parallel" => FresnelParallel,
+    "recurrence" => Recurrence,
+    "recurrence-parallel" => RecurrenceParallel,
+    "simd" => Simd,
+    "simd-parallel" => SimdParallel,
+    "wrp" => Wrp,
+    "polygon" => Polygon,
+    "layer" => Layer,
+});
+
+impl ProcessorKind {
+    /// Whether the processor spreads its work over the rayon thread pool.
+    pub fn is_parallel(self) -> bool {
+        !matches!(
+            self,
+            ProcessorKind::Naive
+                | ProcessorKind::Fresnel
+                | ProcessorKind::Recurrence
+                | ProcessorKind::Simd
+        )
+    }
+
+    /// Whether the field depends on the trig backend. The simd processors
+    /// compute their own sines and cosines, and the layer processor needs
+    /// none.
+    pub fn uses_tri_func(self) -> bool {
+        !matches!(
+            self,
+            ProcessorKind::Simd | ProcessorKind::SimdParallel | ProcessorKind::Layer
+        )
+    }
+
+    /// Whether the processor can remove hidden points, see
+    /// `Settings::occlusion`.
+    pub fn supports_occlusion(self) -> bool {
+        matches!(
+            self,
+            ProcessorKind::Naive
+                | ProcessorKind::NaiveParallel
+                | ProcessorKind::Fresnel
+                | ProcessorKind::FresnelParallel
+        )
+    }
+
+    /// The processor with the settings that apply to it.
+    pub fn build(self, settings: &Settings) -> Box<dyn CghProcessor> {
+        let occlusion = settings.occlusion;
+        match self {
+            ProcessorKind::Naive => Box::new(CghProcessorNaive { occlusion }),
+            ProcessorKind::NaiveParallel => Box::new(CghProcessorNaiveParallel { occlusion }),
+            ProcessorKind::Fresnel => Box::new(CghProcessorFresnel { occlusion }),
+            ProcessorKind::FresnelParallel => Box::new(CghProcessorFresnelParallel { occlusion }),
+            ProcessorKind::Recurrence => Box::new(CghProcessorRecurrence),
+            ProcessorKind::RecurrenceParallel => Box::new(CghProcessorRecurrenceParallel),
+            ProcessorKind::Simd => Box::<CghProcessorSimd>::default(),
+            ProcessorKind::SimdParallel => Box::<CghProcessorSimdParallel>::default(),
+            ProcessorKind::Wrp => Box::new(CghProcessorWrp {
+                distance: settings.wrp_distance,
+            }),
+            ProcessorKind::Polygon => Box::new(CghProcessorPolygon {
+                light: settings.light,
+                ambient: settings.ambient,
+                cull_back_faces: settings.cull_back_faces,
+            }),
+            ProcessorKind::Layer => Box::new(CghProcessorLayer {
+                layers: settings.layers,
+                method: settings.layer_method,
+                random_phase: settings.layer_random_phase,
+            }),
+        }
+    }
+}
+
+/// Settings of the processors and encoders, each used only by the ones it
+/// names. The defaults are theirs.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Settings {
+    /// Hidden-point removal for the naive and fresnel processors.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub occlusion: Option<Occlusion>,
+    /// Polygon: direction towards the light source, in object coordinates.
+    pub light: Point,
+    /// Polygon: share of the intensity that does not depend on the light.
+    pub ambient: f64,
+    /// Polygon: skip the triangles facing away from the hologram.
+    pub cull_back_faces: bool,
+    /// Layer: number of depth slices.
+    pub layers: usize,
+    /// Layer: propagation from the slices to the hologram.
+    pub layer_method: LayerMethod,
+    /// Layer: seed of random phases for every pixel of every slice.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub layer_random_phase: Option<u64>,
+    /// Wrp: distance between the WRP and the nearest point, in pixels.
+    pub wrp_distance: f64,
+    /// Amplitude encoder: reference amplitude relative to the largest object
+    /// amplitude.
+    pub amplitude_bias: f64,
+    /// Error-diffusion encoders: number of levels, by default 2 for
+    /// `error-diffusion` and 16 for `complex-error-diffusion`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub diffusion_levels: Option<u32>,
+    /// Error-diffusion encoders: how the quantization error is spread.
+    pub diffusion_kernel: Kernel,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        let polygon = CghProcessorPolygon::default();
+        let layer = CghProcessorLayer::default();
+        Settings {
+            occlusion: None,
+            light: polygon.light,
+            ambient: polygon.ambient,
+            cull_back_faces: polygon.cull_back_faces,
+            layers: layer.layers,
+            layer_method: layer.method,
+            layer_random_phase: layer.random_phase,
+            wrp_distance: CghProcessorWrp::default().distance,
+            amplitude_bias: Amplitude::default().bias,
+            diffusion_levels: None,
+            diffusion_kernel: Kernel::default(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn validate(&self) -> Result<()> {
+        if let Some(occlusion) = self.occlusion {
+            if occlusion.block_size == 0 {
+                return Err(invalid("settings occlusion block-size must be positive"));
+            }
+            if !(occlusion.point_radius.is_finite() && occlusion.point_radius >= 0.0) {
+                return Err(invalid(
+                    "settings occlusion point-radius must be non-negative",
+                ));
+            }
+        }
+        let light = self.light;
+        if ![light.x, light.y, light.z].iter().all(|v| v.is_finite()) {
+            return Err(invalid("settings light must be finite"));
+        }
+        if !(0.0..=1.0).contains(&self.ambient) {
+            return Err(invalid("settings ambient must be within 0..=1"));
+        }
+        if self.layers == 0 {
+            return Err(invalid("settings layers must be positive"));
+        }
+        if !(self.wrp_distance.is_finite() && self.wrp_distance > 0.0) {
+            return Err(invalid("settings wrp-distance must be positive"));
+        }
+        if !(self.amplitude_bias.is_finite() && self.amplitude_bias > 0.0) {
+            return Err(invalid("settings amplitude-bias must be positive"));
+        }
+        if self.diffusion_levels.is_some_and(|levels| levels < 2) {
+            return Err(invalid("settings diffusion-levels must be at least 2"));
+        }
+
+        Ok(())
+    }
+}
+
+named!(LayerMethod, "layer method", {
+    "asm" => AngularSpectrum,
+    "fresnel" => FresnelConvolution,
+});
+
+named!(Kernel, "diffusion kernel", {
+    "floyd-steinberg" => FloydSteinberg,
+    "jarvis-judice-ninke" => JarvisJudiceNinke,
+});
+
+named!(Interference, "interference", {
+    "intensity" => Intensity,
+    "bipolar" => Bipolar,
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrigKind {
+    #[default]
+    Naive,
+    Lut,
+}
+
+named!(TrigKind, "trig backend", {
+    "naive" => Naive,
+    "lut" => Lut,
+});
+
+named!(LutMode, "LUT mode", {
+    "truncate" => Truncate,
+    "round" => Round,
+    "linear" => Linear,
+    "quadratic" => Quadratic,
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Trig {
+    pub kind: TrigKind,
+    /// Table size for `TrigKind::Lut`, one of `LUT_SIZES`.
+    pub lut_size: usize,
+    /// Lookup mode for `TrigKind::Lut`.
+    pub lut_mode: LutMode,
+}
+
+impl Default for Trig {
+    fn default() -> Self {
+        Trig {
+            kind: TrigKind::Naive,
+            lut_size: LUT_SIZES[0],
+            lut_mode: LutMode::default(),
+        }
+    }
+}
+
+impl Trig {
+    /// Fails with `Error::InvalidJob` if `lut_size` is not one of `LUT_SIZES`,
+    /// whatever the kind.
+    pub fn validate(&self) -> Result<()> {
+        match LUT_SIZES.contains(&self.lut_size) {
+            true => Ok(()),
+            false => Err(invalid(format!(
+                "unsupported trig lut-size {}, expected one of: {:?}",
+                self.lut_size, LUT_SIZES
+            ))),
+        }
+    }
+
+    pub fn build(self) -> Result<Box<dyn TriFunc + Sync>> {
+        self.validate()?;
+        let mode = self.lut_mode;
+        Ok(match (self.kind, self.lut_size) {
+            (TrigKind::Naive, _) => Box::new(TriFuncNaive),
+            (TrigKind::Lut, 256) => Box::new(TriFuncLut::<256>::with_mode(mode)),
+            (TrigKind::Lut, 1024) => Box::new(TriFuncLut::<1024>::with_mode(mode)),
+            (TrigKind::Lut, 4096) => Box::new(TriFuncLut::<4096>::with_mode(mode)),
+            (TrigKind::Lut, 16384) => Box::new(TriFuncLut::<16384>::with_mode(mode)),
+            (TrigKind::Lut, _) => Box::new(TriFuncLut::<65536>::with_mode(mode)),
+        })
+    }
+
+    /// Every LUT size with every mode, for comparing them.
+    pub fn luts() -> Vec<Trig> {
+        LUT_SIZES
+            .iter()
+            .flat_map(|&lut_size| {
+                LutMode::ALL.map(|lut_mode| Trig {
+                    kind: TrigKind::Lut,
+                    lut_size,
+                    lut_mode,
+                })
+            })
+            .collect()
+    }
+
+    /// See `TrigError::measure`.
+    pub fn error(self, samples: usize) -> Result<TrigError> {
+        Ok(TrigError::measure(self.build()?.as_ref(), samples))
+    }
+}
+
+/// `naive`, `lut` (with the smallest table), `lut:SIZE` or `lut:SIZE:MODE`.
+impl FromStr for Trig {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        let mut parts = value.splitn(3, ':');
+        let kind = parts.next().unwrap_or_default();
+        let trig = Trig {
+            kind: kind.parse()?,
+            lut_size: match parts.next() {
+                Some(size) => size
+                    .parse()
+                    .map_err(|_| format!("invalid LUT size `{}`", size))?,
+                None => LUT_SIZES[0],
+            },
+            lut_mode: match parts.next() {
+                Some(mode) => mode.parse()?,
+                None => LutMode::default(),
+            },
+        };
+
+        match LUT_SIZES.contains(&trig.lut_size) {
+            true => Ok(trig),
+            false => Err(format!(
+                "unsupported LUT size, expected one of: {:?}",
+                LUT_SIZES
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Trig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            TrigKind::Naive => write!(f, "{}", self.kind),
+            TrigKind::Lut => match self.lut_mode {
+                LutMode::Truncate => write!(f, "{}:{}", self.kind, self.lut_size),
+                mode => write!(f, "{}:{}:{}", self.kind, self.lut_size, mode),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EncoderKind {
+    #[default]
+    Kinoform,
+    Amplitude,
+    Binary,
+    DoublePhase,
+    ErrorDiffusion,
+    ComplexErrorDiffusion,
+}
+
+named!(EncoderKind, "encoder", {
+    "kinoform" => Kinoform,
+    "amplitude" => Amplitude,
+    "binary" => Binary,
+    "double-phase" => DoublePhase,
+    "error-diffusion" => ErrorDiffusion,
+    "complex-error-diffusion" => ComplexErrorDiffusion,
+});
+
+impl EncoderKind {
+    /// The encoder with the settings that apply to it.
+    pub fn build(self, settings: &Settings) -> Box<dyn Encoder> {
+        let kernel = settings.diffusion_kernel;
+        match self {
+            EncoderKind::Kinoform => Box::new(Kinoform),
+            EncoderKind::Amplitude => Box::new(Amplitude {
+                bias: settings.amplitude_bias,
+            }),
+            EncoderKind::Binary => Box::new(Binary),
+            EncoderKind::DoublePhase => Box::new(DoublePhase),
+            EncoderKind::ErrorDiffusion => Box::new(ErrorDiffusion {
+                kernel,
+                levels: settings
+                    .diffusion_levels
+                    .unwrap_or(ErrorDiffusion::default().levels),
+            }),
+            EncoderKind::ComplexErrorDiffusion => Box::new(ComplexErrorDiffusion {
+                kernel,
+                levels: settings
+                    .diffusion_levels
+                    .unwrap_or(ComplexErrorDiffusion::default().levels),
+            }),
+        }
+    }
+}
+
+/// The fields of `CghConfig`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Hologram {
+    pub width: u32,
+    pub height: u32,
+    /// In meters.
+    pub pixel_pitch: f64,
+    /// In meters.
+    pub wavelength: f64,
+    pub scale: f64,
+    /// Object position in pixels. `None` centers the object 1m in front of
+    /// the hologram.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<Point>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reference: Option<Reference>,
+    /// Red, green and blue wavelengths in meters for a color hologram (see
+    /// `cgh::color`), in place of `wavelength`. Color holograms have 8- or
+    /// 16-bit samples.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wavelengths: Option<[f64; 3]>,
+}
+
+impl Default for Hologram {
+    fn default() -> Self {
+        Hologram {
+            width: 1920,
+            height: 1080,
+            pixel_pitch: 8.0e-6,
+            wavelength: 520.0e-9,
+            scale: 40.0,
+            offset: None,
+            reference: None,
+            wavelengths: None,
+        }
+    }
+}
+
+impl Hologram {
+    pub fn offset(&self) -> Point {
+        self.offset.unwrap_or(Point::new(
+            self.width as f64 / 2.0,
+            self.height as f64 / 2.0,
+            1.0 / self.pixel_pitch,
+        ))
+    }
+
+    pub fn config(&self) -> CghConfig {
+        CghConfig {
+            image_width: self.width,
+            image_height: self.height,
+            pixel_pitch: self.pixel_pitch,
+            wavelength: self.wavelength,
+            offset: self.offset(),
+            scalar: self.scale,
+            reference: self.reference,
+        }
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.width == 0 || self.height == 0 {
+            return Err(invalid("hologram width and height must be non-zero"));
+        }
+        if !(self.pixel_pitch.is_finite() && self.pixel_pitch > 0.0) {
+            return Err(invalid("hologram pixel-pitch must be positive"));
+        }
+        if !(self.wavelength.is_finite() && self.wavelength > 0.0) {
+            return Err(invalid("hologram wavelength must be positive"));
+        }
+        if let Some(wavelengths) = self.wavelengths {
+            if !wavelengths.iter().all(|w| w.is_finite() && *w > 0.0) {
+                return Err(invalid("hologram wavelengths must be positive"));
+            }
+        }
+        if !(self.scale.is_finite() && self.scale != 0.0) {
+            return Err(invalid("hologram scale must be non-zero"));
+        }
+        let offset = self.offset();
+        if ![offset.x, offset.y, offset.z].iter().all(|v| v.is_finite()) {
+            return Err(invalid("hologram offset must be finite"));
+        }
+        if offset.z == 0.0 {
+            return Err(invalid(
+                "hologram offset.z must be non-zero, the object cannot lie on the hologram",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Placement of the object, applied to its points in object units: scaling
+/// first, then rotation about the x, y and z axes in that order, then
+/// translation.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Transform {
+    pub scale: f64,
+    /// Angles in degrees.
+    pub rotate: [f64; 3],
+    pub translate: [f64; 3],
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform {
+            scale: 1.0,
+            rotate: [0.0; 3],
+            translate: [0.0; 3],
+        }
+    }
+}
+
+impl Transform {
+    pub fn apply(&self, point: Point) -> Point {
+        let [x, y, z] = self.rotate.map(f64::to_radians);
+        let Point {
+            x: px,
+            y: py,
+            z: pz,
+        } = point * self.scale;
+
+        let (py, pz) = (py * x.cos() - pz * x.sin(), py * x.sin() + pz * x.cos());
+        let (pz, px) = (pz * y.cos() - px * y.sin(), pz * y.sin() + px * y.cos());
+        let (px, py) = (px * z.cos() - py * z.sin(), px * z.sin() + py * z.cos());
+
+        let [tx, ty, tz] = self.translate;
+        Point::new(px + tx, py + ty, pz + tz)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Input {
+    pub path: PathBuf,
+    /// Taken from the extension of `path` if not given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<ObjectFormat>,
+    #[serde(default)]
+    pub transform: Transform,
+    /// Covers the faces of meshes with points this far apart (after the
+    /// transform), see `Object3d::sample_faces`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sample_spacing: Option<f64>,
+    /// Seed of random initial phases, see `Object3d::with_random_phases`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub random_phase: Option<u64>,
+    /// Reads `path` as the image of an RGB-D pair with this depth map,
+    /// instead of as an object file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub depth: Option<Depth>,
+    /// See `Object3d::with_position_colors`.
+    #[serde(default)]
+    pub color_by_position: bool,
+}
+
+/// Depth map of an RGB-D input, see `RgbdImage::to_object`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Depth {
+    /// BMP or PGM depth map.
+    pub path: PathBuf,
+    /// Depth of the farthest level, in object units.
+    pub range: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Output {
+    pub path: PathBuf,
+    /// Taken from the extension of `path` if not given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<ImageFormat>,
+    #[serde(default)]
+    pub sample_depth: SampleDepth,
+    /// Bits per pixel of grayscale BMP output with 8-bit samples: 1, 4 or 8,
+    /// for binary and 16-level SLMs. See `bmp::Image::set_bits_per_pixel`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bits_per_pixel: Option<u16>,
+    /// Whether `Job::run` writes the resolved job next to the output.
+    #[serde(default = "enabled")]
+    pub sidecar: bool,
+}
+
+fn enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Job {
+    #[serde(default)]
+    pub processor: ProcessorKind,
+    #[serde(default)]
+    pub encoder: EncoderKind,
+    #[serde(default)]
+    pub trig: Trig,
+    #[serde(default)]
+    pub settings: Settings,
+    #[serde(default)]
+    pub hologram: Hologram,
+    pub input: Input,
+    pub output: Output,
+}
+
+fn invalid<S: Into<String>>(message: S) -> Error {
+    Error::InvalidJob(message.into())
+}
+
+impl Job {
+    /// Parses and validates a TOML job.
+    pub fn from_toml(text: &str) -> Result<Self> {
+        let job: Job = toml::from_str(text).map_err(|error| invalid(error.to_string()))?;
+        job.validate()?;
+        Ok(job)
+    }
+
+    /// Parses and validates a JSON job.
+    pub fn from_json(text: &str) -> Result<Self> {
+        let job: Job = serde_json::from_str(text).map_err(|error| invalid(error.to_string()))?;
+        job.validate()?;
+        Ok(job)
+    }
+
+    /// Opens a `.toml` or `.json` job file.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(OsStr::to_str)
+            .map(str::to_ascii_lowercase);
+        let parse = match extension.as_deref() {
+            Some("toml") => Self::from_toml,
+            Some("json") => Self::from_json,
+            _ => {
+                return Err(Error::UnsupportedFormat(
+                    "job files must be .toml or .json".to_string(),
+                )
+                .with_path(path))
+            }
+        };
+
+        fs::read_to_string(path)
+            .map_err(Error::from)
+            .and_then(|text| parse(&text))
+            .map_err(|error| error.with_path(path))
+    }
+
+    pub fn to_toml(&self) -> String {
+        toml::to_string(self).expect("jobs serialize to TOML")
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("jobs serialize to JSON")
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        self.hologram.validate()?;
+        self.settings.validate()?;
+        if self.settings.occlusion.is_some() && !self.processor.supports_occlusion() {
+            return Err(invalid(format!(
+                "the {} processor does not support occlusion",
+                self.processor
+            )));
+        }
+        let float = matches!(
+            self.output.sample_depth,
+            SampleDepth::Float | SampleDepth::FloatLevel
+        );
+        if self.hologram.wavelengths.is_some() && float {
+            return Err(invalid("color holograms need 8- or 16-bit samples"));
+        }
+
+        if let Some(bits_per_pixel) = self.output.bits_per_pixel {
+            if !matches!(bits_per_pixel, 1 | 4 | 8) {
+                return Err(invalid("output bits-per-pixel must be 1, 4 or 8"));
+            }
+            let bmp = self
+                .output_format()
+                .map_or(true, |format| format == ImageFormat::Bmp);
+            let gray8 = self.output.sample_depth == SampleDepth::Eight
+                && self.hologram.wavelengths.is_none();
+            if !(bmp && gray8) {
+                return Err(invalid(
+                    "output bits-per-pixel needs grayscale BMP output with 8-bit samples",
+                ));
+            }
+        }
+
+        self.trig.validate()?;
+
+        let transform = &self.input.transform;
+        let values = [transform.scale]
+            .into_iter()
+            .chain(transform.rotate)
+            .chain(transform.translate);
+        if !values.into_iter().all(f64::is_finite) || transform.scale == 0.0 {
+            return Err(invalid(
+                "input transform must be finite with a non-zero scale",
+            ));
+        }
+        if let Some(spacing) = self.input.sample_spacing {
+            if !(spacing.is_finite() && spacing > 0.0) {
+                return Err(invalid("input sample-spacing must be positive"));
+            }
+        }
+        if let Some(depth) = &self.input.depth {
+            if self.input.format.is_some() {
+                return Err(invalid("input format does not apply to RGB-D inputs"));
+            }
+            if !(depth.range.is_finite() && depth.range != 0.0) {
+                return Err(invalid("input depth range must be non-zero"));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The job with every default made explicit: the object offset and the
+    /// input and output formats. Running it gives the same hologram even if
+    /// the defaults change.
+    pub fn resolved(&self) -> Result<Job> {
+        let mut job = self.clone();
+        job.hologram.offset = Some(self.hologram.offset());
+        job.input.format = match self.input.depth {
+            Some(_) => None,
+            None => Some(self.input_format()?),
+        };
+        job.output.format = Some(self.output_format()?);
+
+        Ok(job)
+    }
+
+    fn input_format(&self) -> Result<ObjectFormat> {
+        let path = &self.input.path;
+        self.input
+            .format
+            .or_else(|| ObjectFormat::from_path(path))
+            .ok_or_else(|| unknown_format(path))
+    }
+
+    fn output_format(&self) -> Result<ImageFormat> {
+        let path = &self.output.path;
+        self.output
+            .format
+            .or_else(|| ImageFormat::from_path(path))
+            .ok_or_else(|| unknown_format(path))
+    }
+
+    /// Reads the input object and applies the transform, face sampling,
+    /// random phases and position colors, in that order.
+    pub fn object(&self) -> Result<Object3d> {
+        let transform = self.input.transform;
+        let object = match &self.input.depth {
+            Some(depth) => RgbdImage::open(&self.input.path, &depth.path)?.to_object(depth.range),
+            None => Object3d::open_as(&self.input.path, self.input_format()?)?,
+        };
+        let object = object.map_points(|point| transform.apply(point));
+        let object = match self.input.sample_spacing {
+            Some(spacing) => object.sample_faces(spacing),
+            None => object,
+        };
+
+        let object = match self.input.random_phase {
+            Some(seed) => object.with_random_phases(seed),
+            None => object,
+        };
+
+        Ok(match self.input.color_by_position {
+            true => object.with_position_colors(),
+            false => object,
+        })
+    }
+
+    /// Computes the hologram without writing it.
+    pub fn raster(&self) -> Result<Raster> {
+        self.validate()?;
+        let object = self.object()?;
+        if self.processor == ProcessorKind::Polygon && object.faces().is_empty() {
+            return Err(Error::InvalidObject(
+                "the polygon processor needs an object with faces".to_string(),
+            ));
+        }
+
+        let processor = self.processor.build(&self.settings);
+        let encoder = self.encoder.build(&self.settings);
+        let tri_func = self.trig.build()?;
+        let raster = |config: &CghConfig, object: &Object3d| {
+            generate_cgh_raster(
+                processor.as_ref(),
+                encoder.as_ref(),
+                tri_func.as_ref(),
+                config,
+                object,
+                self.output.sample_depth,
+            )
+        };
+
+        match self.hologram.wavelengths {
+            Some(wavelengths) => {
+                let config = ColorConfig {
+                    config: self.hologram.config(),
+                    wavelengths,
+                };
+                let [red, green, blue] =
+                    [0, 1, 2].map(|index| raster(&config.channel(index), &object.channel(index)));
+                Raster::from_channels(&red, &green, &blue)
+            }
+            None => {
+                let mut raster = raster(&self.hologram.config(), &object);
+                if let Some(bits_per_pixel) = self.output.bits_per_pixel {
+                    raster.set_bits_per_pixel(bits_per_pixel);
+                }
+                Ok(raster)
+            }
+        }
+    }
+
+    /// Path of the sidecar written by `run`: the output path with the
+    /// extension `job.toml`.
+    pub fn sidecar_path(&self) -> PathBuf {
+        self.output.path.with_extension("job.toml")
+    }
+
+    /// Computes the hologram and writes it to `output.path`, followed by the
+    /// resolved job if `output.sidecar` is set. The output directory must
+    /// exist.
+    pub fn run(&self) -> Result<()> {
+        let resolved = self.resolved()?;
+        let raster = resolved.raster()?;
+        raster.save(&resolved.output.path, resolved.output_format()?)?;
+
+        if resolved.output.sidecar {
+            let path = self.sidecar_path();
+            let text = format!(
+                "# Resolved job for {}, written by holography {}.\n\n{}",
+                resolved.output.path.display(),
+                env!("CARGO_PKG_VERSION"),
+                resolved.to_toml()
+            );
+            fs::write(&path, text).map_err(|error| Error::from(error).with_path(path))?;
+        }
+
+        Ok(())
+    }
+}
+
+fn unknown_format(path: &Path) -> Error {
+    Error::UnsupportedFormat(format!("cannot tell the format of {}", path.display()))
+}
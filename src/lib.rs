@@ -1,5 +1,18 @@
+pub mod bench;
 pub mod bmp;
 pub mod cgh;
+pub mod error;
+pub mod fft;
+pub mod ifta;
+pub mod job;
 pub mod lut;
+pub mod mesh;
 pub mod object;
+pub mod ply;
 pub mod point;
+pub mod propagation;
+pub mod raster;
+pub mod reconstruction;
+pub mod rgbd;
+
+mod random;
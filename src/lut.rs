@@ -1,8 +1,29 @@
+use num::Complex;
+use serde::{Deserialize, Serialize};
 use std::f64::consts::PI;
 
+/// Sine and cosine of a phase given in cycles (`1.0` is a full turn).
 pub trait TriFunc {
     fn sin(&self, rad: f64) -> f64;
     fn cos(&self, rad: f64) -> f64;
+
+    /// `(sin, cos)`. Implementations that share work between the two
+    /// override it.
+    #[inline]
+    fn sincos(&self, rad: f64) -> (f64, f64) {
+        (self.sin(rad), self.cos(rad))
+    }
+
+    /// `cos + i sin` of every phase of `rad` into `out`, of the same length.
+    /// One call per buffer instead of two per value, in a loop that
+    /// implementations override so that it can be inlined and vectorized.
+    fn eval_batch(&self, rad: &[f64], out: &mut [Complex<f64>]) {
+        assert_eq!(rad.len(), out.len(), "phase and output buffers differ");
+        for (out, &rad) in out.iter_mut().zip(rad) {
+            let (sin, cos) = self.sincos(rad);
+            *out = Complex::new(cos, sin);
+        }
+    }
 }
 
 pub struct TriFuncNaive;
@@ -29,26 +50,118 @@ impl TriFunc for TriFuncNaive {
     fn cos(&self, rad: f64) -> f64 {
         (rad.fract() * 2.0 * PI).cos()
     }
+
+    #[inline]
+    fn sincos(&self, rad: f64) -> (f64, f64) {
+        (rad.fract() * 2.0 * PI).sin_cos()
+    }
+
+    fn eval_batch(&self, rad: &[f64], out: &mut [Complex<f64>]) {
+        assert_eq!(rad.len(), out.len(), "phase and output buffers differ");
+        for (out, &rad) in out.iter_mut().zip(rad) {
+            let (sin, cos) = (rad.fract() * 2.0 * PI).sin_cos();
+            *out = Complex::new(cos, sin);
+        }
+    }
+}
+
+/// How `TriFuncLut` turns a phase between two table entries into a value.
+/// With `h = 2π / SIZE` the step of the table, the largest error is about:
+///
+/// | mode        | max error   |
+/// |-------------|-------------|
+/// | `Truncate`  | `h`         |
+/// | `Round`     | `h / 2`     |
+/// | `Linear`    | `h² / 8`    |
+/// | `Quadratic` | `h³ / 16`   |
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LutMode {
+    /// The entry below the phase, which is always rounded down.
+    #[default]
+    Truncate,
+    /// The nearest entry.
+    Round,
+    /// Linear interpolation between the entries on either side.
+    Linear,
+    /// Quadratic interpolation through the nearest entry and its neighbors.
+    Quadratic,
+}
+
+impl LutMode {
+    pub const ALL: [LutMode; 4] = [
+        LutMode::Truncate,
+        LutMode::Round,
+        LutMode::Linear,
+        LutMode::Quadratic,
+    ];
 }
 
+/// Sine and cosine from a table of `SIZE` entries over one cycle. The table
+/// is on the heap, so large sizes can be used anywhere.
 #[derive(Debug)]
 pub struct TriFuncLut<const SIZE: usize> {
-    sin: [f64; SIZE],
-    cos: [f64; SIZE],
+    /// `[sin, cos]` of `(i - 1) · 2π / SIZE` at index `i`, for the entries
+    /// `-1..=SIZE + 1`, so that interpolation never wraps around.
+    table: Box<[[f64; 2]]>,
+    mode: LutMode,
 }
 
 impl<const SIZE: usize> TriFuncLut<SIZE> {
     pub fn new() -> Self {
-        let mut sin = [0.0; SIZE];
-        let mut cos = [0.0; SIZE];
+        Self::with_mode(LutMode::default())
+    }
 
-        for i in 0..SIZE {
-            let rad = (2.0 * PI / SIZE as f64) * i as f64;
-            sin[i] = rad.sin();
-            cos[i] = rad.cos();
-        }
+    pub fn with_mode(mode: LutMode) -> Self {
+        assert!(SIZE > 0, "a lookup table needs at least one entry");
+
+        let table = (-1..=SIZE as isize + 1)
+            .map(|i| {
+                let rad = (2.0 * PI / SIZE as f64) * i as f64;
+                [rad.sin(), rad.cos()]
+            })
+            .collect();
+
+        Self { table, mode }
+    }
 
-        Self { sin, cos }
+    pub fn mode(&self) -> LutMode {
+        self.mode
+    }
+
+    /// `[sin, cos]` of the phase `rad` in cycles.
+    #[inline]
+    fn lookup(&self, rad: f64) -> [f64; 2] {
+        self.lookup_with(self.mode, rad)
+    }
+
+    /// `lookup` with the mode as an argument, which is constant in the
+    /// loops of `eval_batch` once inlined.
+    #[inline(always)]
+    fn lookup_with(&self, mode: LutMode, rad: f64) -> [f64; 2] {
+        // In 0..=SIZE: the fractional part is 1 for tiny negative phases.
+        let x = (rad - rad.floor()) * SIZE as f64;
+        let entry = |k: usize| self.table[k + 1];
+
+        match mode {
+            LutMode::Truncate => entry(x as usize),
+            LutMode::Round => entry(x.round() as usize),
+            LutMode::Linear => {
+                let k = x as usize;
+                let t = x - k as f64;
+                let ([s0, c0], [s1, c1]) = (entry(k), entry(k + 1));
+                [s0 + t * (s1 - s0), c0 + t * (c1 - c0)]
+            }
+            LutMode::Quadratic => {
+                let k = x.round() as usize;
+                let t = x - k as f64;
+                let ([sm, cm], [s0, c0], [sp, cp]) = (self.table[k], entry(k), entry(k + 1));
+                let quadratic = |m: f64, o: f64, p: f64| {
+                    o + t * (p - m) / 2.0 + t * t * (p - 2.0 * o + m) / 2.0
+                };
+                [quadratic(sm, s0, sp), quadratic(cm, c0, cp)]
+            }
+        }
     }
 }
 
@@ -61,13 +174,63 @@ impl<const SIZE: usize> Default for TriFuncLut<SIZE> {
 impl<const SIZE: usize> TriFunc for TriFuncLut<SIZE> {
     #[inline]
     fn sin(&self, rad: f64) -> f64 {
-        let k = (rad.fract() * SIZE as f64) as usize;
-        self.sin[k]
+        self.lookup(rad)[0]
     }
 
     #[inline]
     fn cos(&self, rad: f64) -> f64 {
-        let k = (rad.fract() * SIZE as f64) as usize;
-        self.cos[k]
+        self.lookup(rad)[1]
+    }
+
+    #[inline]
+    fn sincos(&self, rad: f64) -> (f64, f64) {
+        let [sin, cos] = self.lookup(rad);
+        (sin, cos)
+    }
+
+    fn eval_batch(&self, rad: &[f64], out: &mut [Complex<f64>]) {
+        assert_eq!(rad.len(), out.len(), "phase and output buffers differ");
+        let mut batch = |mode| {
+            for (out, &rad) in out.iter_mut().zip(rad) {
+                let [sin, cos] = self.lookup_with(mode, rad);
+                *out = Complex::new(cos, sin);
+            }
+        };
+
+        match self.mode {
+            LutMode::Truncate => batch(LutMode::Truncate),
+            LutMode::Round => batch(LutMode::Round),
+            LutMode::Linear => batch(LutMode::Linear),
+            LutMode::Quadratic => batch(LutMode::Quadratic),
+        }
+    }
+}
+
+/// Error of a `TriFunc` against `f64::sin_cos`, as the distance between the
+/// points `(cos, sin)` and the exact ones on the unit circle. For small
+/// errors it is about the phase error in radians plus the amplitude error.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct TrigError {
+    pub max: f64,
+    pub rms: f64,
+}
+
+impl TrigError {
+    /// Measures `tri_func` at `samples` phases spread evenly over a cycle,
+    /// along the golden ratio sequence so they never line up with a table.
+    pub fn measure(tri_func: &dyn TriFunc, samples: usize) -> Self {
+        let golden = (5f64.sqrt() - 1.0) / 2.0;
+        let (max, sum) = (0..samples).fold((0.0f64, 0.0), |(max, sum), i| {
+            let rad = (i as f64 * golden).fract();
+            let (sin, cos) = tri_func.sincos(rad);
+            let (exact_sin, exact_cos) = (2.0 * PI * rad).sin_cos();
+            let error = (sin - exact_sin).hypot(cos - exact_cos);
+            (max.max(error), sum + error * error)
+        });
+
+        TrigError {
+            max,
+            rms: (sum / samples.max(1) as f64).sqrt(),
+        }
     }
 }
@@ -0,0 +1,284 @@
+//! Loaders for triangle meshes in Wavefront OBJ and STL (ASCII and binary)
+//! formats.
+//!
+//! Only geometry is read: OBJ `v` and `f` records (polygons are split into
+//! triangle fans, texture and normal indices are ignored) and STL facets,
+//! whose shared vertices are merged. `Object3d::sample_faces` turns a mesh
+//! into a point cloud for the point-based processors.
+
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+
+use crate::error::{Error, Result};
+use crate::object::{Object3d, MAX_INITIAL_CAPACITY};
+use crate::point::Point;
+
+/// Size of one binary STL facet: normal, three vertices and an attribute.
+const STL_FACET_SIZE: usize = 50;
+
+fn invalid<S: Into<String>>(message: S) -> Error {
+    Error::InvalidObject(message.into())
+}
+
+fn parse_f64(token: Option<&str>, line: usize) -> Result<f64> {
+    token
+        .and_then(|token| token.parse().ok())
+        .ok_or_else(|| invalid(format!("invalid coordinate on line {}", line)))
+}
+
+/// Merges vertices with identical coordinates.
+#[derive(Default)]
+struct Vertices {
+    points: Vec<Point>,
+    indices: HashMap<[u64; 3], usize>,
+}
+
+impl Vertices {
+    fn insert(&mut self, point: Point) -> usize {
+        let key = [point.x.to_bits(), point.y.to_bits(), point.z.to_bits()];
+        let points = &mut self.points;
+        *self.indices.entry(key).or_insert_with(|| {
+            points.push(point);
+            points.len() - 1
+        })
+    }
+}
+
+impl Object3d {
+    pub fn from_obj_reader<R: BufRead>(reader: &mut R) -> Result<Self> {
+        let mut points = Vec::new();
+        let mut faces = Vec::new();
+
+        for (number, line) in reader.lines().enumerate() {
+            let line = line?;
+            let number = number + 1;
+            let mut tokens = line.split_whitespace();
+
+            match tokens.next() {
+                Some("v") => {
+                    let x = parse_f64(tokens.next(), number)?;
+                    let y = parse_f64(tokens.next(), number)?;
+                    let z = parse_f64(tokens.next(), number)?;
+                    points.push(Point::new(x, y, z));
+                }
+                Some("f") => {
+                    let polygon = tokens
+                        .map(|token| obj_index(token, points.len(), number))
+                        .collect::<Result<Vec<_>>>()?;
+                    if polygon.len() < 3 {
+                        return Err(invalid(format!(
+                            "face with fewer than 3 vertices on line {}",
+                            number
+                        )));
+                    }
+                    for pair in polygon[1..].windows(2) {
+                        faces.push([polygon[0], pair[0], pair[1]]);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(Object3d::new(points).with_faces(faces))
+    }
+
+    pub fn open_obj<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        File::open(path)
+            .map_err(Error::from)
+            .and_then(|file| Self::from_obj_reader(&mut BufReader::new(file)))
+            .map_err(|error| error.with_path(path))
+    }
+
+    /// Reads an ASCII or binary STL file. Files starting with `solid` are
+    /// taken as ASCII unless they fail to parse and are not text or have
+    /// exactly the size of a binary file, as some exporters also start binary
+    /// headers with `solid`.
+    pub fn from_stl_reader<R: Read>(reader: &mut R) -> Result<Self> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+
+        if bytes.starts_with(b"solid") {
+            match stl_ascii(&bytes) {
+                Ok(object) => return Ok(object),
+                Err(error) if !is_binary_stl(&bytes) && std::str::from_utf8(&bytes).is_ok() => {
+                    return Err(error)
+                }
+                Err(_) => {}
+            }
+        }
+
+        stl_binary(&bytes)
+    }
+
+    pub fn open_stl<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        File::open(path)
+            .map_err(Error::from)
+            .and_then(|file| Self::from_stl_reader(&mut BufReader::new(file)))
+            .map_err(|error| error.with_path(path))
+    }
+
+    /// Covers the faces with points about `spacing` apart, for the
+    /// point-based processors. The vertices come first and the faces are kept,
+    /// e.g. as occluders (see `cgh::occlusion`). Amplitudes and colors are
+    /// interpolated from the vertices; phases start at zero.
+    pub fn sample_faces(&self, spacing: f64) -> Object3d {
+        assert!(spacing > 0.0, "spacing must be positive");
+        let colors = self.colors();
+
+        let mut points = self.points().clone();
+        let mut amplitudes = self.amplitudes().clone();
+        let mut sampled_colors = colors.cloned().unwrap_or_default();
+        // Points on shared edges are generated by both faces.
+        let mut seen = HashSet::new();
+        let key =
+            |point: Point| [point.x, point.y, point.z].map(|v| (v / spacing * 1e6).round() as i64);
+        seen.extend(self.points().iter().map(|&point| key(point)));
+
+        for &face in self.faces() {
+            let [a, b, c] = face.map(|index| self.points()[index]);
+            let length = |p: Point, q: Point| p.distance(&q);
+            let steps = (length(a, b).max(length(b, c)).max(length(c, a)) / spacing)
+                .ceil()
+                .max(1.0) as usize;
+
+            for i in 0..=steps {
+                for j in 0..=steps - i {
+                    let weights = [
+                        (steps - i - j) as f64 / steps as f64,
+                        i as f64 / steps as f64,
+                        j as f64 / steps as f64,
+                    ];
+                    let point = a * weights[0] + b * weights[1] + c * weights[2];
+                    if !seen.insert(key(point)) {
+                        continue;
+                    }
+
+                    let interpolate =
+                        |values: &[f64]| (0..3).map(|k| weights[k] * values[face[k]]).sum();
+                    points.push(point);
+                    amplitudes.push(interpolate(self.amplitudes()));
+                    if let Some(colors) = colors {
+                        sampled_colors.push([0, 1, 2].map(|channel| {
+                            (0..3).map(|k| weights[k] * colors[face[k]][channel]).sum()
+                        }));
+                    }
+                }
+            }
+        }
+
+        let object = Object3d::new(points)
+            .with_amplitudes(amplitudes)
+            .with_faces(self.faces().clone());
+        match colors {
+            Some(_) => object.with_colors(sampled_colors),
+            None => object,
+        }
+    }
+}
+
+/// Resolves an OBJ vertex reference (`v`, `v/vt`, `v//vn` or `v/vt/vn`, 1-based
+/// or negative for relative) to a 0-based index.
+fn obj_index(token: &str, count: usize, line: usize) -> Result<usize> {
+    let error = || invalid(format!("invalid vertex index `{}` on line {}", token, line));
+    let index: i64 = token
+        .split('/')
+        .next()
+        .unwrap()
+        .parse()
+        .map_err(|_| error())?;
+
+    let resolved = match index {
+        index if index > 0 => index - 1,
+        index if index < 0 => count as i64 + index,
+        _ => return Err(error()),
+    };
+    match resolved >= 0 && (resolved as usize) < count {
+        true => Ok(resolved as usize),
+        false => Err(error()),
+    }
+}
+
+fn is_binary_stl(bytes: &[u8]) -> bool {
+    bytes.len() >= 84 && {
+        let count = u32::from_le_bytes(bytes[80..84].try_into().unwrap()) as usize;
+        bytes.len() == 84 + count * STL_FACET_SIZE
+    }
+}
+
+fn stl_ascii(bytes: &[u8]) -> Result<Object3d> {
+    let text = std::str::from_utf8(bytes).map_err(|_| invalid("STL is not valid text"))?;
+    let mut vertices = Vertices::default();
+    let mut faces = Vec::new();
+    let mut facet = Vec::with_capacity(3);
+
+    for (number, line) in text.lines().enumerate() {
+        let number = number + 1;
+        let mut tokens = line.split_whitespace();
+
+        match tokens.next() {
+            Some("vertex") => {
+                let x = parse_f64(tokens.next(), number)?;
+                let y = parse_f64(tokens.next(), number)?;
+                let z = parse_f64(tokens.next(), number)?;
+                facet.push(vertices.insert(Point::new(x, y, z)));
+            }
+            Some("endloop") => {
+                if facet.len() != 3 {
+                    return Err(invalid(format!(
+                        "facet without 3 vertices on line {}",
+                        number
+                    )));
+                }
+                faces.push([facet[0], facet[1], facet[2]]);
+                facet.clear();
+            }
+            _ => {}
+        }
+    }
+
+    if faces.is_empty() {
+        return Err(invalid("STL without facets"));
+    }
+
+    Ok(Object3d::new(vertices.points).with_faces(faces))
+}
+
+fn stl_binary(bytes: &[u8]) -> Result<Object3d> {
+    if bytes.len() < 84 {
+        return Err(invalid("truncated STL header"));
+    }
+    let count = u32::from_le_bytes(bytes[80..84].try_into().unwrap()) as usize;
+    let records = bytes[84..].chunks_exact(STL_FACET_SIZE);
+    if records.len() < count {
+        return Err(invalid(format!(
+            "truncated STL: {} of {} facets",
+            records.len(),
+            count
+        )));
+    }
+
+    let mut vertices = Vertices::default();
+    let mut faces = Vec::with_capacity(count.min(MAX_INITIAL_CAPACITY));
+    for record in records.take(count) {
+        let value = |i: usize| {
+            let offset = 12 + 4 * i;
+            f32::from_le_bytes(record[offset..offset + 4].try_into().unwrap()) as f64
+        };
+        let mut face = [0; 3];
+        for (vertex, index) in face.iter_mut().enumerate() {
+            let point = Point::new(
+                value(3 * vertex),
+                value(3 * vertex + 1),
+                value(3 * vertex + 2),
+            );
+            *index = vertices.insert(point);
+        }
+        faces.push(face);
+    }
+
+    Ok(Object3d::new(vertices.points).with_faces(faces))
+}
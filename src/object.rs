@@ -1,47 +1,279 @@
+use crate::error::{Error, Result};
 use crate::point::Point;
+use crate::random::SplitMix64;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+use std::ffi::OsStr;
 use std::fs::File;
-use std::io::{self, Read};
+use std::io::{self, BufReader, Read};
 use std::path::Path;
 
+/// Size of one `.3d` point record: three little-endian `i32` coordinates.
+const POINT_RECORD_SIZE: u64 = 12;
+
+/// Upper bound for the capacity reserved up front, so that a bogus point count
+/// cannot trigger a huge allocation before the stream runs out.
+pub(crate) const MAX_INITIAL_CAPACITY: usize = 1 << 16;
+
+/// File formats objects can be read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ObjectFormat {
+    /// The point list read by `Object3d::open` (`.3d`).
+    #[serde(rename = "3d")]
+    Points,
+    Ply,
+    Obj,
+    Stl,
+}
+
+impl ObjectFormat {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let extension = path
+            .as_ref()
+            .extension()
+            .and_then(OsStr::to_str)?
+            .to_ascii_lowercase();
+
+        match extension.as_str() {
+            "3d" => Some(ObjectFormat::Points),
+            "ply" => Some(ObjectFormat::Ply),
+            "obj" => Some(ObjectFormat::Obj),
+            "stl" => Some(ObjectFormat::Stl),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Object3d {
     points: Vec<Point>,
+    amplitudes: Vec<f64>,
+    phases: Vec<f64>,
+    colors: Option<Vec<[f64; 3]>>,
+    faces: Vec<[usize; 3]>,
 }
 
 impl Object3d {
+    /// Creates an object whose points all have unit amplitude and zero
+    /// initial phase.
+    pub fn new(points: Vec<Point>) -> Self {
+        let amplitudes = vec![1.0; points.len()];
+        let phases = vec![0.0; points.len()];
+        Object3d {
+            points,
+            amplitudes,
+            phases,
+            colors: None,
+            faces: Vec::new(),
+        }
+    }
+
+    /// Replaces the per-point amplitudes, which the CGH processors use to
+    /// weight each point's contribution.
+    pub fn with_amplitudes(mut self, amplitudes: Vec<f64>) -> Self {
+        assert_eq!(
+            amplitudes.len(),
+            self.points.len(),
+            "one amplitude per point is required"
+        );
+        self.amplitudes = amplitudes;
+        self
+    }
+
+    /// Sets per-point red, green and blue amplitudes for color holograms, see
+    /// `channel`.
+    pub fn with_colors(mut self, colors: Vec<[f64; 3]>) -> Self {
+        assert_eq!(
+            colors.len(),
+            self.points.len(),
+            "one color per point is required"
+        );
+        self.colors = Some(colors);
+        self
+    }
+
+    /// Colors the points by position, red along x, green along y and blue
+    /// along z, each from 0 at the lowest coordinate to 1 at the highest. Gives
+    /// objects without colors something to show in color holograms.
+    pub fn with_position_colors(self) -> Self {
+        let (min, max) = self.points.iter().fold(
+            (
+                Point::new(f64::MAX, f64::MAX, f64::MAX),
+                Point::new(f64::MIN, f64::MIN, f64::MIN),
+            ),
+            |(min, max), p| {
+                (
+                    Point::new(min.x.min(p.x), min.y.min(p.y), min.z.min(p.z)),
+                    Point::new(max.x.max(p.x), max.y.max(p.y), max.z.max(p.z)),
+                )
+            },
+        );
+        let scale = |value: f64, min: f64, max: f64| match max > min {
+            true => (value - min) / (max - min),
+            false => 1.0,
+        };
+        let colors = self
+            .points
+            .iter()
+            .map(|p| {
+                [
+                    scale(p.x, min.x, max.x),
+                    scale(p.y, min.y, max.y),
+                    scale(p.z, min.z, max.z),
+                ]
+            })
+            .collect();
+
+        self.with_colors(colors)
+    }
+
+    /// Sets triangles, as indices into `points`, for the polygon-based
+    /// processor. The point-based processors only use the vertices.
+    pub fn with_faces(mut self, faces: Vec<[usize; 3]>) -> Self {
+        assert!(
+            faces
+                .iter()
+                .flatten()
+                .all(|&index| index < self.points.len()),
+            "face index out of range"
+        );
+        self.faces = faces;
+        self
+    }
+
+    /// Replaces the per-point initial phases (in radians), which are added to
+    /// the propagation phase of each point. They are stored wrapped to
+    /// `[0, 2π)`.
+    pub fn with_phases(mut self, phases: Vec<f64>) -> Self {
+        assert_eq!(
+            phases.len(),
+            self.points.len(),
+            "one phase per point is required"
+        );
+        self.phases = phases
+            .into_iter()
+            .map(|phase| phase.rem_euclid(2.0 * PI))
+            .collect();
+        self
+    }
+
+    /// Assigns uniformly distributed random initial phases, reproducible for a
+    /// given `seed`, to diffuse the reconstructed light (speckle control).
+    pub fn with_random_phases(self, seed: u64) -> Self {
+        let mut random = SplitMix64::new(seed);
+        let phases = (0..self.points.len())
+            .map(|_| 2.0 * PI * random.next_f64())
+            .collect();
+        self.with_phases(phases)
+    }
+
+    /// Moves every point with `transform`, keeping amplitudes, phases, colors
+    /// and faces.
+    pub fn map_points<F: FnMut(Point) -> Point>(mut self, transform: F) -> Self {
+        self.points = self.points.into_iter().map(transform).collect();
+        self
+    }
+
     pub fn points(&self) -> &Vec<Point> {
         &self.points
     }
 
+    pub fn amplitudes(&self) -> &Vec<f64> {
+        &self.amplitudes
+    }
+
+    /// Initial phases in radians, within `[0, 2π)`.
+    pub fn phases(&self) -> &Vec<f64> {
+        &self.phases
+    }
+
+    /// Per-point red, green and blue amplitudes, if the object has colors.
+    pub fn colors(&self) -> Option<&Vec<[f64; 3]>> {
+        self.colors.as_ref()
+    }
+
+    /// Triangles as indices into `points`; empty for point clouds.
+    pub fn faces(&self) -> &Vec<[usize; 3]> {
+        &self.faces
+    }
+
+    /// The object as seen by one color channel (0 red, 1 green, 2 blue): its
+    /// amplitudes are that channel of `colors`. Objects without colors are
+    /// returned with their amplitudes unchanged.
+    pub fn channel(&self, index: usize) -> Object3d {
+        let amplitudes = match &self.colors {
+            Some(colors) => colors.iter().map(|color| color[index]).collect(),
+            None => self.amplitudes.clone(),
+        };
+
+        Object3d {
+            points: self.points.clone(),
+            amplitudes,
+            phases: self.phases.clone(),
+            colors: None,
+            faces: self.faces.clone(),
+        }
+    }
+
     pub fn size(&self) -> usize {
         self.points.len()
     }
 
-    pub fn from_reader<R: Read>(reader: &mut R) -> io::Result<Self> {
+    pub fn from_reader<R: Read>(reader: &mut R) -> Result<Self> {
         let mut buffer = [0; 4];
 
         reader.read_exact(&mut buffer)?;
-        let size = u32::from_le_bytes(buffer);
+        let size = u32::from_le_bytes(buffer) as usize;
+
+        let mut points = Vec::with_capacity(size.min(MAX_INITIAL_CAPACITY));
 
-        let mut points = Vec::with_capacity(size as usize);
+        for index in 0..size {
+            let mut record = [0; POINT_RECORD_SIZE as usize];
+            reader
+                .read_exact(&mut record)
+                .map_err(|error| match error.kind() {
+                    io::ErrorKind::UnexpectedEof => Error::TruncatedPoint { index, count: size },
+                    _ => Error::Io(error),
+                })?;
 
-        for _ in 0..size {
-            reader.read_exact(&mut buffer)?;
-            let x = i32::from_le_bytes(buffer);
-            reader.read_exact(&mut buffer)?;
-            let y = i32::from_le_bytes(buffer);
-            reader.read_exact(&mut buffer)?;
-            let z = i32::from_le_bytes(buffer);
+            let x = i32::from_le_bytes(record[0..4].try_into().unwrap());
+            let y = i32::from_le_bytes(record[4..8].try_into().unwrap());
+            let z = i32::from_le_bytes(record[8..12].try_into().unwrap());
 
             let point = Point::new(x as f64, y as f64, z as f64);
             points.push(point);
         }
 
-        Ok(Object3d { points })
+        Ok(Object3d::new(points))
+    }
+
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        Self::open_checked(path).map_err(|error| error.with_path(path))
+    }
+
+    pub fn open_as<P: AsRef<Path>>(path: P, format: ObjectFormat) -> Result<Self> {
+        match format {
+            ObjectFormat::Points => Self::open(path),
+            ObjectFormat::Ply => Self::open_ply(path),
+            ObjectFormat::Obj => Self::open_obj(path),
+            ObjectFormat::Stl => Self::open_stl(path),
+        }
     }
 
-    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
-        let mut file = File::open(path)?;
-        Self::from_reader(&mut file)
+    fn open_checked(path: &Path) -> Result<Self> {
+        let file = File::open(path)?;
+        let stream_len = file.metadata()?.len();
+        let mut reader = BufReader::new(file);
+
+        let mut buffer = [0; 4];
+        reader.read_exact(&mut buffer)?;
+        let count = u32::from_le_bytes(buffer) as u64;
+        if 4 + count * POINT_RECORD_SIZE > stream_len {
+            return Err(Error::ImplausiblePointCount { count, stream_len });
+        }
+
+        Self::from_reader(&mut (&buffer[..]).chain(reader))
     }
 }
@@ -0,0 +1,355 @@
+//! Loader for PLY point clouds (ASCII and binary, either endianness).
+//!
+//! Only the `vertex` element is used. Besides the `x`, `y` and `z` coordinates,
+//! the `red`/`green`/`blue` color and `intensity` properties are mapped to a
+//! per-point amplitude: the brightness is the color's luminance multiplied by
+//! the intensity (each normalized to 0..1 for integer types), and the amplitude
+//! is its square root. Points without either property get unit amplitude.
+//! Colored points also get per-channel amplitudes, computed the same way from
+//! each channel instead of the luminance.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use crate::error::{Error, Result};
+use crate::object::{Object3d, MAX_INITIAL_CAPACITY};
+use crate::point::Point;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scalar {
+    Int8,
+    UInt8,
+    Int16,
+    UInt16,
+    Int32,
+    UInt32,
+    Float32,
+    Float64,
+}
+
+impl Scalar {
+    fn parse(name: &str) -> Result<Self> {
+        match name {
+            "char" | "int8" => Ok(Scalar::Int8),
+            "uchar" | "uint8" => Ok(Scalar::UInt8),
+            "short" | "int16" => Ok(Scalar::Int16),
+            "ushort" | "uint16" => Ok(Scalar::UInt16),
+            "int" | "int32" => Ok(Scalar::Int32),
+            "uint" | "uint32" => Ok(Scalar::UInt32),
+            "float" | "float32" => Ok(Scalar::Float32),
+            "double" | "float64" => Ok(Scalar::Float64),
+            _ => Err(invalid(format!("unknown property type `{}`", name))),
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Scalar::Int8 | Scalar::UInt8 => 1,
+            Scalar::Int16 | Scalar::UInt16 => 2,
+            Scalar::Int32 | Scalar::UInt32 | Scalar::Float32 => 4,
+            Scalar::Float64 => 8,
+        }
+    }
+
+    /// Scale that maps the type's full range to 0..1 for brightness values.
+    fn unit_scale(self) -> f64 {
+        match self {
+            Scalar::UInt8 => u8::MAX as f64,
+            Scalar::UInt16 => u16::MAX as f64,
+            _ => 1.0,
+        }
+    }
+
+    fn decode(self, bytes: &[u8], format: Format) -> f64 {
+        macro_rules! decode {
+            ($type:ty) => {{
+                let bytes = bytes.try_into().unwrap();
+                match format {
+                    Format::BinaryBigEndian => <$type>::from_be_bytes(bytes) as f64,
+                    _ => <$type>::from_le_bytes(bytes) as f64,
+                }
+            }};
+        }
+
+        match self {
+            Scalar::Int8 => decode!(i8),
+            Scalar::UInt8 => decode!(u8),
+            Scalar::Int16 => decode!(i16),
+            Scalar::UInt16 => decode!(u16),
+            Scalar::Int32 => decode!(i32),
+            Scalar::UInt32 => decode!(u32),
+            Scalar::Float32 => decode!(f32),
+            Scalar::Float64 => decode!(f64),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Property {
+    Scalar { name: String, kind: Scalar },
+    List { count: Scalar, item: Scalar },
+}
+
+#[derive(Debug)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+impl Element {
+    fn index_of(&self, names: &[&str]) -> Option<(usize, Scalar)> {
+        self.properties
+            .iter()
+            .enumerate()
+            .find_map(|(index, property)| match property {
+                Property::Scalar { name, kind } if names.contains(&name.as_str()) => {
+                    Some((index, *kind))
+                }
+                _ => None,
+            })
+    }
+}
+
+fn invalid<S: Into<String>>(message: S) -> Error {
+    Error::InvalidObject(message.into())
+}
+
+fn read_header<R: BufRead>(reader: &mut R) -> Result<(Format, Vec<Element>)> {
+    let mut line = String::new();
+    let mut next_line = |line: &mut String| -> Result<()> {
+        line.clear();
+        if reader.read_line(line)? == 0 {
+            return Err(invalid("unexpected end of PLY header"));
+        }
+        Ok(())
+    };
+
+    next_line(&mut line)?;
+    if line.trim_end() != "ply" {
+        return Err(invalid("missing `ply` magic"));
+    }
+
+    let mut format = None;
+    let mut elements = Vec::<Element>::new();
+
+    loop {
+        next_line(&mut line)?;
+        let mut words = line.split_whitespace();
+
+        match words.next() {
+            Some("format") => {
+                format = Some(match words.next() {
+                    Some("ascii") => Format::Ascii,
+                    Some("binary_little_endian") => Format::BinaryLittleEndian,
+                    Some("binary_big_endian") => Format::BinaryBigEndian,
+                    other => {
+                        return Err(Error::UnsupportedFormat(format!(
+                            "PLY format `{}`",
+                            other.unwrap_or_default()
+                        )))
+                    }
+                });
+            }
+            Some("element") => {
+                let name = words.next().ok_or_else(|| invalid("unnamed element"))?;
+                let count = words
+                    .next()
+                    .and_then(|count| count.parse().ok())
+                    .ok_or_else(|| invalid(format!("invalid count for element `{}`", name)))?;
+                elements.push(Element {
+                    name: name.to_string(),
+                    count,
+                    properties: Vec::new(),
+                });
+            }
+            Some("property") => {
+                let element = elements
+                    .last_mut()
+                    .ok_or_else(|| invalid("property outside of an element"))?;
+                let property = match words.next() {
+                    Some("list") => Property::List {
+                        count: Scalar::parse(words.next().unwrap_or_default())?,
+                        item: Scalar::parse(words.next().unwrap_or_default())?,
+                    },
+                    Some(kind) => Property::Scalar {
+                        kind: Scalar::parse(kind)?,
+                        name: words
+                            .next()
+                            .ok_or_else(|| invalid("unnamed property"))?
+                            .to_string(),
+                    },
+                    None => return Err(invalid("empty property declaration")),
+                };
+                element.properties.push(property);
+            }
+            Some("end_header") => break,
+            Some("comment") | Some("obj_info") | None => {}
+            Some(keyword) => return Err(invalid(format!("unknown header keyword `{}`", keyword))),
+        }
+    }
+
+    let format = format.ok_or_else(|| invalid("missing format declaration"))?;
+    Ok((format, elements))
+}
+
+/// Reads the properties of one element record, returning the scalar values
+/// (list properties are skipped and yield NaN).
+fn read_record<R: BufRead>(
+    reader: &mut R,
+    format: Format,
+    element: &Element,
+    line: &mut String,
+    values: &mut Vec<f64>,
+) -> Result<bool> {
+    values.clear();
+
+    if format == Format::Ascii {
+        line.clear();
+        if reader.read_line(line)? == 0 {
+            return Ok(false);
+        }
+        let mut words = line.split_whitespace().map(|word| {
+            word.parse::<f64>()
+                .map_err(|_| invalid(format!("invalid number `{}`", word)))
+        });
+        for property in &element.properties {
+            let mut next = || {
+                words
+                    .next()
+                    .unwrap_or(Err(invalid("missing property value")))
+            };
+            match property {
+                Property::Scalar { .. } => values.push(next()?),
+                Property::List { .. } => {
+                    let count = next()? as usize;
+                    for _ in 0..count {
+                        next()?;
+                    }
+                    values.push(f64::NAN);
+                }
+            }
+        }
+        return Ok(true);
+    }
+
+    let mut buffer = [0; 8];
+    let mut read_scalar = |reader: &mut R, kind: Scalar| -> Result<Option<f64>> {
+        let bytes = &mut buffer[..kind.size()];
+        match reader.read_exact(bytes) {
+            Ok(()) => Ok(Some(kind.decode(bytes, format))),
+            Err(error) if error.kind() == std::io::ErrorKind::UnexpectedEof => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    };
+
+    for property in &element.properties {
+        let value = match *property {
+            Property::Scalar { kind, .. } => read_scalar(reader, kind)?,
+            Property::List { count, item } => match read_scalar(reader, count)? {
+                // The count is not trusted: the record is truncated at the
+                // first missing item.
+                Some(count) => {
+                    for _ in 0..count as usize {
+                        if read_scalar(reader, item)?.is_none() {
+                            return Ok(false);
+                        }
+                    }
+                    Some(f64::NAN)
+                }
+                None => None,
+            },
+        };
+        match value {
+            Some(value) => values.push(value),
+            None => return Ok(false),
+        }
+    }
+
+    Ok(true)
+}
+
+impl Object3d {
+    pub fn from_ply_reader<R: BufRead>(reader: &mut R) -> Result<Self> {
+        let (format, elements) = read_header(reader)?;
+
+        let mut points = Vec::new();
+        let mut brightness = Vec::new();
+        let mut colors = Vec::new();
+        let mut line = String::new();
+        let mut values = Vec::new();
+
+        for element in &elements {
+            if element.name != "vertex" {
+                for _ in 0..element.count {
+                    if !read_record(reader, format, element, &mut line, &mut values)? {
+                        return Err(invalid(format!("truncated `{}` element", element.name)));
+                    }
+                }
+                continue;
+            }
+
+            let coordinate = |name| {
+                element
+                    .index_of(&[name])
+                    .map(|(index, _)| index)
+                    .ok_or_else(|| invalid(format!("vertex without `{}` property", name)))
+            };
+            let (x, y, z) = (coordinate("x")?, coordinate("y")?, coordinate("z")?);
+            let color = [
+                element.index_of(&["red", "diffuse_red", "r"]),
+                element.index_of(&["green", "diffuse_green", "g"]),
+                element.index_of(&["blue", "diffuse_blue", "b"]),
+            ];
+            let intensity = element.index_of(&["intensity", "scalar_intensity"]);
+
+            points.reserve(element.count.min(MAX_INITIAL_CAPACITY));
+            brightness.reserve(element.count.min(MAX_INITIAL_CAPACITY));
+
+            for index in 0..element.count {
+                if !read_record(reader, format, element, &mut line, &mut values)? {
+                    return Err(Error::TruncatedPoint {
+                        index,
+                        count: element.count,
+                    });
+                }
+
+                points.push(Point::new(values[x], values[y], values[z]));
+
+                let mut value = 1.0;
+                if let Some((i, kind)) = intensity {
+                    value *= values[i] / kind.unit_scale();
+                }
+                if let [Some((r, kind)), Some((g, _)), Some((b, _))] = color {
+                    let rgb = [values[r], values[g], values[b]].map(|c| c / kind.unit_scale());
+                    colors.push(rgb.map(|c| (c * value).max(0.0).sqrt()));
+                    value *= 0.299 * rgb[0] + 0.587 * rgb[1] + 0.114 * rgb[2];
+                }
+                brightness.push(value);
+            }
+        }
+
+        let amplitudes = brightness.into_iter().map(|b| b.max(0.0).sqrt()).collect();
+        let object = Object3d::new(points).with_amplitudes(amplitudes);
+        match colors.len() == object.size() && !colors.is_empty() {
+            true => Ok(object.with_colors(colors)),
+            false => Ok(object),
+        }
+    }
+
+    pub fn open_ply<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        File::open(path)
+            .map_err(Error::from)
+            .and_then(|file| Self::from_ply_reader(&mut BufReader::new(file)))
+            .map_err(|error| error.with_path(path))
+    }
+}
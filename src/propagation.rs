@@ -0,0 +1,227 @@
+//! Scalar diffraction between parallel planes using FFTs.
+//!
+//! Fields are sampled on a regular grid with pitch `pixel_pitch`; the
+//! propagation distance is in meters and may be negative (back-propagation).
+//! Propagating by zero returns the field unchanged, with every method.
+
+use ndarray::parallel::prelude::*;
+use ndarray::{s, Array2, Axis};
+use num::{Complex, Zero};
+use std::f64::consts::PI;
+
+use crate::fft::{self, Direction};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    /// Band-limited angular spectrum method; exact for any distance.
+    AngularSpectrum,
+    /// Fresnel diffraction as a convolution with the sampled impulse response.
+    /// The output pitch equals the input pitch.
+    FresnelConvolution,
+    /// Fresnel diffraction with a single FFT. The output pitch becomes
+    /// `λ|z| / (N p)`, see `Propagator::single_fft_pitch`.
+    FresnelSingleFft,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Propagator {
+    pub pixel_pitch: f64,
+    pub wavelength: f64,
+    /// Zero-padding factor applied to both dimensions; 2 avoids the circular
+    /// wrap-around of the FFT-based convolutions, 1 disables padding.
+    pub padding: usize,
+    /// Drops the components of the transfer function (or impulse response)
+    /// that would be undersampled and alias.
+    pub band_limit: bool,
+}
+
+impl Propagator {
+    pub fn new(pixel_pitch: f64, wavelength: f64) -> Self {
+        Propagator {
+            pixel_pitch,
+            wavelength,
+            padding: 2,
+            band_limit: true,
+        }
+    }
+
+    pub fn propagate(
+        &self,
+        field: &Array2<Complex<f64>>,
+        distance: f64,
+        method: Method,
+    ) -> Array2<Complex<f64>> {
+        match method {
+            Method::AngularSpectrum => self.angular_spectrum(field, distance),
+            Method::FresnelConvolution => self.fresnel_convolution(field, distance),
+            Method::FresnelSingleFft => self.fresnel_single_fft(field, distance),
+        }
+    }
+
+    /// Pixel pitch of the field produced by `fresnel_single_fft` for a field
+    /// of `len` samples along an axis: the input pitch at zero distance.
+    pub fn single_fft_pitch(&self, len: usize, distance: f64) -> f64 {
+        if distance == 0.0 {
+            return self.pixel_pitch;
+        }
+        self.wavelength * distance.abs() / ((len * self.padding.max(1)) as f64 * self.pixel_pitch)
+    }
+
+    pub fn angular_spectrum(
+        &self,
+        field: &Array2<Complex<f64>>,
+        distance: f64,
+    ) -> Array2<Complex<f64>> {
+        let (height, width) = field.dim();
+        let mut padded = self.pad(field);
+        let (padded_height, padded_width) = padded.dim();
+
+        fft::fft2(&mut padded, Direction::Forward);
+
+        let df_x = 1.0 / (padded_width as f64 * self.pixel_pitch);
+        let df_y = 1.0 / (padded_height as f64 * self.pixel_pitch);
+        // Matsushima & Shimobaba, "Band-limited angular spectrum method".
+        let limit = |df: f64| match self.band_limit {
+            true => 1.0 / (self.wavelength * ((2.0 * df * distance).powi(2) + 1.0).sqrt()),
+            false => f64::INFINITY,
+        };
+        let (limit_x, limit_y) = (limit(df_x), limit(df_y));
+        let k2 = self.wavelength.powi(-2);
+
+        padded
+            .axis_iter_mut(Axis(0))
+            .into_par_iter()
+            .enumerate()
+            .for_each(|(ky, mut row)| {
+                let fy = fft::frequency_index(ky, padded_height) * df_y;
+                for (kx, value) in row.iter_mut().enumerate() {
+                    let fx = fft::frequency_index(kx, padded_width) * df_x;
+                    let w2 = k2 - fx.powi(2) - fy.powi(2);
+                    if w2 <= 0.0 || fx.abs() > limit_x || fy.abs() > limit_y {
+                        *value = Complex::zero();
+                        continue;
+                    }
+                    let cycles = (distance * w2.sqrt()).fract();
+                    *value *= Complex::from_polar(1.0, 2.0 * PI * cycles);
+                }
+            });
+
+        fft::fft2(&mut padded, Direction::Inverse);
+
+        padded.slice(s![..height, ..width]).to_owned()
+    }
+
+    pub fn fresnel_convolution(
+        &self,
+        field: &Array2<Complex<f64>>,
+        distance: f64,
+    ) -> Array2<Complex<f64>> {
+        if distance == 0.0 {
+            return field.clone();
+        }
+        let (height, width) = field.dim();
+        let mut padded = self.pad(field);
+        let (padded_height, padded_width) = padded.dim();
+
+        let lambda_z = self.wavelength * distance;
+        // Beyond this radius the chirp of the impulse response exceeds the
+        // Nyquist frequency of the grid.
+        let radius = match self.band_limit {
+            true => lambda_z.abs() / (2.0 * self.pixel_pitch),
+            false => f64::INFINITY,
+        };
+        let scale = self.pixel_pitch.powi(2) / lambda_z;
+        let carrier = Complex::from_polar(1.0, 2.0 * PI * (distance / self.wavelength).fract());
+
+        let mut kernel = Array2::<Complex<f64>>::zeros((padded_height, padded_width));
+        kernel
+            .axis_iter_mut(Axis(0))
+            .into_par_iter()
+            .enumerate()
+            .for_each(|(j, mut row)| {
+                let y = fft::frequency_index(j, padded_height) * self.pixel_pitch;
+                for (i, value) in row.iter_mut().enumerate() {
+                    let x = fft::frequency_index(i, padded_width) * self.pixel_pitch;
+                    let r2 = x.powi(2) + y.powi(2);
+                    if r2.sqrt() > radius {
+                        continue;
+                    }
+                    // exp(ikz) / (iλz) · exp(iπ r² / λz) · p²
+                    *value = carrier
+                        * Complex::new(0.0, -scale)
+                        * Complex::from_polar(1.0, PI * r2 / lambda_z);
+                }
+            });
+
+        fft::fft2(&mut padded, Direction::Forward);
+        fft::fft2(&mut kernel, Direction::Forward);
+        padded.zip_mut_with(&kernel, |a, &b| *a *= b);
+        fft::fft2(&mut padded, Direction::Inverse);
+
+        padded.slice(s![..height, ..width]).to_owned()
+    }
+
+    /// The field is taken to be centered on the optical axis, and so is the
+    /// result, which is cropped back to the input size. At zero distance the
+    /// field is returned as it is, with its own pitch.
+    pub fn fresnel_single_fft(
+        &self,
+        field: &Array2<Complex<f64>>,
+        distance: f64,
+    ) -> Array2<Complex<f64>> {
+        if distance == 0.0 {
+            return field.clone();
+        }
+        let (height, width) = field.dim();
+        let (padded_height, padded_width) =
+            (height * self.padding.max(1), width * self.padding.max(1));
+        let lambda_z = self.wavelength * distance;
+
+        // Input samples are placed around index 0 so that the FFT treats the
+        // field center as the origin.
+        let mut padded = Array2::<Complex<f64>>::zeros((padded_height, padded_width));
+        for ((j, i), &value) in field.indexed_iter() {
+            let y = j as isize - (height / 2) as isize;
+            let x = i as isize - (width / 2) as isize;
+            let r2 = ((x as f64).powi(2) + (y as f64).powi(2)) * self.pixel_pitch.powi(2);
+            let row = y.rem_euclid(padded_height as isize) as usize;
+            let column = x.rem_euclid(padded_width as isize) as usize;
+            padded[(row, column)] = value * Complex::from_polar(1.0, PI * r2 / lambda_z);
+        }
+
+        // exp(-2πi x1 x2 / λz) is a forward transform for z > 0 and an
+        // (unnormalized) inverse one for z < 0.
+        let direction = match distance >= 0.0 {
+            true => Direction::Forward,
+            false => Direction::Inverse,
+        };
+        fft::fft2(&mut padded, direction);
+        if direction == Direction::Inverse {
+            padded *= Complex::from((padded_height * padded_width) as f64);
+        }
+
+        let output_pitch_x = self.single_fft_pitch(width, distance);
+        let output_pitch_y = self.single_fft_pitch(height, distance);
+        let scale = self.pixel_pitch.powi(2) / lambda_z;
+        let carrier = Complex::from_polar(1.0, 2.0 * PI * (distance / self.wavelength).fract())
+            * Complex::new(0.0, -scale);
+
+        Array2::from_shape_fn((height, width), |(j, i)| {
+            let y = j as isize - (height / 2) as isize;
+            let x = i as isize - (width / 2) as isize;
+            let r2 = (x as f64 * output_pitch_x).powi(2) + (y as f64 * output_pitch_y).powi(2);
+            let row = y.rem_euclid(padded_height as isize) as usize;
+            let column = x.rem_euclid(padded_width as isize) as usize;
+            carrier * Complex::from_polar(1.0, PI * r2 / lambda_z) * padded[(row, column)]
+        })
+    }
+
+    fn pad(&self, field: &Array2<Complex<f64>>) -> Array2<Complex<f64>> {
+        let (height, width) = field.dim();
+        let padding = self.padding.max(1);
+
+        let mut padded = Array2::<Complex<f64>>::zeros((height * padding, width * padding));
+        padded.slice_mut(s![..height, ..width]).assign(field);
+        padded
+    }
+}
@@ -0,0 +1,32 @@
+const fn make_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut c = n as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 {
+                0xedb8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+    table
+}
+
+const TABLE: [u32; 256] = make_table();
+
+/// CRC-32 as used by PNG chunks.
+pub fn crc32(chunks: &[&[u8]]) -> u32 {
+    let mut c = !0u32;
+    for chunk in chunks {
+        for &byte in *chunk {
+            c = TABLE[((c ^ byte as u32) & 0xff) as usize] ^ (c >> 8);
+        }
+    }
+    !c
+}
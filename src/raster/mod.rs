@@ -0,0 +1,284 @@
+//! Output of images in formats other than 8-bit BMP, for results that need
+//! more than 256 levels or the raw floating point phase. BMP and PNM files can
+//! also be read with `Raster::open`, e.g. 16-bit depth maps (see `rgbd`).
+
+mod crc;
+pub mod png;
+pub mod pnm;
+pub mod tiff;
+pub mod zlib;
+
+use serde::{Deserialize, Serialize};
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::bmp;
+use crate::error::{Error, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageFormat {
+    Bmp,
+    Png,
+    /// Binary PGM for grayscale and PPM for RGB rasters.
+    Pnm,
+    Tiff,
+}
+
+impl ImageFormat {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let extension = path
+            .as_ref()
+            .extension()
+            .and_then(OsStr::to_str)?
+            .to_ascii_lowercase();
+
+        match extension.as_str() {
+            "bmp" => Some(ImageFormat::Bmp),
+            "png" => Some(ImageFormat::Png),
+            "pgm" | "ppm" | "pnm" => Some(ImageFormat::Pnm),
+            "tif" | "tiff" => Some(ImageFormat::Tiff),
+            _ => None,
+        }
+    }
+}
+
+/// Pixel samples in row-major order, top row first. RGB samples are
+/// interleaved.
+#[derive(Debug, Clone)]
+pub enum Pixels {
+    Gray8(Vec<u8>),
+    Gray16(Vec<u16>),
+    Rgb8(Vec<u8>),
+    Rgb16(Vec<u16>),
+    GrayF32(Vec<f32>),
+}
+
+impl Pixels {
+    pub fn channels(&self) -> usize {
+        match self {
+            Pixels::Gray8(_) | Pixels::Gray16(_) | Pixels::GrayF32(_) => 1,
+            Pixels::Rgb8(_) | Pixels::Rgb16(_) => 3,
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Pixels::Gray8(samples) | Pixels::Rgb8(samples) => samples.len(),
+            Pixels::Gray16(samples) | Pixels::Rgb16(samples) => samples.len(),
+            Pixels::GrayF32(samples) => samples.len(),
+        }
+    }
+
+    fn to_be_bytes(&self) -> Vec<u8> {
+        match self {
+            Pixels::Gray8(samples) | Pixels::Rgb8(samples) => samples.clone(),
+            Pixels::Gray16(samples) | Pixels::Rgb16(samples) => {
+                samples.iter().flat_map(|v| v.to_be_bytes()).collect()
+            }
+            Pixels::GrayF32(samples) => samples.iter().flat_map(|v| v.to_be_bytes()).collect(),
+        }
+    }
+
+    fn to_le_bytes(&self) -> Vec<u8> {
+        match self {
+            Pixels::Gray8(samples) | Pixels::Rgb8(samples) => samples.clone(),
+            Pixels::Gray16(samples) | Pixels::Rgb16(samples) => {
+                samples.iter().flat_map(|v| v.to_le_bytes()).collect()
+            }
+            Pixels::GrayF32(samples) => samples.iter().flat_map(|v| v.to_le_bytes()).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Raster {
+    width: u32,
+    height: u32,
+    pixels: Pixels,
+    pixel_pitch: Option<f64>,
+    bits_per_pixel: Option<u16>,
+}
+
+impl Raster {
+    pub fn new(width: u32, height: u32, pixels: Pixels) -> Self {
+        assert_eq!(
+            pixels.len(),
+            width as usize * height as usize * pixels.channels(),
+            "pixel buffer does not match the raster dimensions"
+        );
+
+        Raster {
+            width,
+            height,
+            pixels,
+            pixel_pitch: None,
+            bits_per_pixel: None,
+        }
+    }
+
+    /// Interleaves three 8- or 16-bit grayscale rasters of the same size into
+    /// the red, green and blue channels. The pixel pitch is taken from `red`.
+    pub fn from_channels(red: &Raster, green: &Raster, blue: &Raster) -> Result<Self> {
+        if [green, blue]
+            .iter()
+            .any(|c| (c.width, c.height) != (red.width, red.height))
+        {
+            return Err(Error::InvalidImage(
+                "color channels differ in size".to_string(),
+            ));
+        }
+
+        fn interleave<T: Copy>(channels: [&[T]; 3]) -> Vec<T> {
+            (0..channels[0].len())
+                .flat_map(|i| channels.map(|channel| channel[i]))
+                .collect()
+        }
+        let pixels = match (&red.pixels, &green.pixels, &blue.pixels) {
+            (Pixels::Gray8(r), Pixels::Gray8(g), Pixels::Gray8(b)) => {
+                Pixels::Rgb8(interleave([r, g, b]))
+            }
+            (Pixels::Gray16(r), Pixels::Gray16(g), Pixels::Gray16(b)) => {
+                Pixels::Rgb16(interleave([r, g, b]))
+            }
+            _ => {
+                return Err(Error::UnsupportedFormat(
+                    "color channels must be 8- or 16-bit grayscale".to_string(),
+                ))
+            }
+        };
+
+        let mut raster = Raster::new(red.width, red.height, pixels);
+        raster.pixel_pitch = red.pixel_pitch;
+
+        Ok(raster)
+    }
+
+    #[inline]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[inline]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    #[inline]
+    pub fn pixels(&self) -> &Pixels {
+        &self.pixels
+    }
+
+    #[inline]
+    pub fn pixel_pitch(&self) -> Option<f64> {
+        self.pixel_pitch
+    }
+
+    /// Records the physical pixel pitch (in meters) in formats that support it.
+    pub fn set_pixel_pitch(&mut self, pixel_pitch: f64) {
+        self.pixel_pitch = Some(pixel_pitch);
+    }
+
+    #[inline]
+    pub fn bits_per_pixel(&self) -> Option<u16> {
+        self.bits_per_pixel
+    }
+
+    /// Writes 8-bit grayscale BMP output with 1, 4 or 8 bits per pixel, see
+    /// `bmp::Image::set_bits_per_pixel`. Other formats keep every bit.
+    pub fn set_bits_per_pixel(&mut self, bits_per_pixel: u16) {
+        self.bits_per_pixel = Some(bits_per_pixel);
+    }
+
+    pub fn to_writer<W: Write>(&self, destination: &mut W, format: ImageFormat) -> Result<()> {
+        match format {
+            ImageFormat::Bmp => match &self.pixels {
+                Pixels::Rgb8(samples) => Ok(self.to_rgb_bmp(samples)?.to_writer(destination)?),
+                _ => Ok(self.to_bmp()?.to_writer(destination)?),
+            },
+            ImageFormat::Png => png::to_writer(self, destination, zlib::Compression::Fixed),
+            ImageFormat::Pnm => pnm::to_writer(self, destination),
+            ImageFormat::Tiff => tiff::to_writer(self, destination),
+        }
+    }
+
+    /// Reads a BMP file (as 8-bit grayscale) or a PNM file.
+    pub fn open<P: AsRef<Path>>(path: P, format: ImageFormat) -> Result<Self> {
+        let path = path.as_ref();
+        match format {
+            ImageFormat::Bmp => bmp::Image::open(path).map(|image| Raster::from(&image)),
+            ImageFormat::Pnm => pnm::open(path),
+            ImageFormat::Png | ImageFormat::Tiff => Err(Error::UnsupportedFormat(format!(
+                "cannot read {:?} images",
+                format
+            ))
+            .with_path(path)),
+        }
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P, format: ImageFormat) -> Result<()> {
+        let path = path.as_ref();
+        let write = || -> Result<()> {
+            let mut file = BufWriter::new(File::create(path)?);
+            self.to_writer(&mut file, format)?;
+            Ok(file.flush()?)
+        };
+
+        write().map_err(|error| error.with_path(path))
+    }
+
+    fn to_bmp(&self) -> Result<bmp::Image> {
+        let Pixels::Gray8(samples) = &self.pixels else {
+            return Err(Error::UnsupportedFormat(
+                "BMP output only supports 8-bit samples".to_string(),
+            ));
+        };
+
+        let mut image = bmp::Image::new(self.width, self.height)?;
+        if let Some(pixel_pitch) = self.pixel_pitch {
+            image.set_pixel_pitch(pixel_pitch);
+        }
+        if let Some(bits_per_pixel) = self.bits_per_pixel {
+            image.set_bits_per_pixel(bits_per_pixel)?;
+        }
+        for ((x, y), &color) in image.coordinates().zip(samples) {
+            image.set_pixel(x, y, color);
+        }
+
+        Ok(image)
+    }
+
+    fn to_rgb_bmp(&self, samples: &[u8]) -> Result<bmp::RgbImage> {
+        if self.bits_per_pixel.is_some_and(|bits| bits != 8) {
+            return Err(Error::UnsupportedFormat(
+                "RGB BMP output has 8 bits per channel".to_string(),
+            ));
+        }
+
+        let mut image = bmp::RgbImage::new(self.width, self.height)?;
+        if let Some(pixel_pitch) = self.pixel_pitch {
+            image.set_pixel_pitch(pixel_pitch);
+        }
+        let coordinates = (0..self.height).flat_map(|y| (0..self.width).map(move |x| (x, y)));
+        for ((x, y), rgb) in coordinates.zip(samples.chunks_exact(3)) {
+            image.set_pixel(x, y, [rgb[0], rgb[1], rgb[2]]);
+        }
+
+        Ok(image)
+    }
+}
+
+impl From<&bmp::Image> for Raster {
+    fn from(image: &bmp::Image) -> Self {
+        let samples = image
+            .coordinates()
+            .map(|(x, y)| image.get_pixel(x, y))
+            .collect();
+        let mut raster = Raster::new(image.width(), image.height(), Pixels::Gray8(samples));
+        raster.pixel_pitch = image.pixel_pitch();
+
+        raster
+    }
+}
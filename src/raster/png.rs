@@ -0,0 +1,142 @@
+use std::io::{self, Write};
+
+use super::crc::crc32;
+use super::zlib::{self, Compression};
+use super::{Pixels, Raster};
+
+use crate::error::{Error, Result};
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+const COLOR_TYPE_GRAY: u8 = 0;
+const COLOR_TYPE_RGB: u8 = 2;
+
+fn write_chunk<W: Write>(destination: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    destination.write_all(&(data.len() as u32).to_be_bytes())?;
+    destination.write_all(kind)?;
+    destination.write_all(data)?;
+    destination.write_all(&crc32(&[kind, data]).to_be_bytes())?;
+
+    Ok(())
+}
+
+/// Applies the filter among the five PNG filter types that minimizes the sum
+/// of absolute differences, prefixing each row with its filter type byte.
+fn filter_rows(bytes: &[u8], stride: usize, pixel_size: usize) -> Vec<u8> {
+    let mut output = Vec::with_capacity(bytes.len() + bytes.len() / stride.max(1));
+    let zero = vec![0; stride];
+    let mut candidates = vec![vec![0; stride]; 5];
+
+    for (y, row) in bytes.chunks_exact(stride).enumerate() {
+        let above = if y == 0 {
+            &zero[..]
+        } else {
+            &bytes[(y - 1) * stride..y * stride]
+        };
+
+        for i in 0..stride {
+            let a = if i >= pixel_size {
+                row[i - pixel_size]
+            } else {
+                0
+            };
+            let b = above[i];
+            let c = if i >= pixel_size {
+                above[i - pixel_size]
+            } else {
+                0
+            };
+            let x = row[i];
+
+            candidates[0][i] = x;
+            candidates[1][i] = x.wrapping_sub(a);
+            candidates[2][i] = x.wrapping_sub(b);
+            candidates[3][i] = x.wrapping_sub(((a as u16 + b as u16) / 2) as u8);
+            candidates[4][i] = x.wrapping_sub(paeth(a, b, c));
+        }
+
+        let (filter, filtered) = candidates
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, candidate)| {
+                candidate
+                    .iter()
+                    .map(|&v| (v as i8).unsigned_abs() as u64)
+                    .sum::<u64>()
+            })
+            .unwrap();
+
+        output.push(filter as u8);
+        output.extend_from_slice(filtered);
+    }
+
+    output
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let pa = (p - a as i16).abs();
+    let pb = (p - b as i16).abs();
+    let pc = (p - c as i16).abs();
+
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+pub fn to_writer<W: Write>(
+    raster: &Raster,
+    destination: &mut W,
+    compression: Compression,
+) -> Result<()> {
+    let (bit_depth, color_type, channels) = match raster.pixels() {
+        Pixels::Gray8(_) => (8, COLOR_TYPE_GRAY, 1),
+        Pixels::Gray16(_) => (16, COLOR_TYPE_GRAY, 1),
+        Pixels::Rgb8(_) => (8, COLOR_TYPE_RGB, 3),
+        Pixels::Rgb16(_) => (16, COLOR_TYPE_RGB, 3),
+        Pixels::GrayF32(_) => {
+            return Err(Error::UnsupportedFormat(
+                "PNG cannot store floating point samples".to_string(),
+            ))
+        }
+    };
+
+    let bytes = raster.pixels().to_be_bytes();
+    let pixel_size = channels * bit_depth / 8;
+    let stride = raster.width() as usize * pixel_size;
+    let filtered = if stride == 0 {
+        vec![0; raster.height() as usize]
+    } else {
+        filter_rows(&bytes, stride, pixel_size)
+    };
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&raster.width().to_be_bytes());
+    header.extend_from_slice(&raster.height().to_be_bytes());
+    header.extend_from_slice(&[bit_depth as u8, color_type, 0, 0, 0]);
+
+    destination.write_all(&SIGNATURE)?;
+    write_chunk(destination, b"IHDR", &header)?;
+
+    if let Some(pixel_pitch) = raster.pixel_pitch() {
+        let pixels_per_meter = ((1.0 / pixel_pitch).round() as u32).to_be_bytes();
+        let mut physical = Vec::with_capacity(9);
+        physical.extend_from_slice(&pixels_per_meter);
+        physical.extend_from_slice(&pixels_per_meter);
+        physical.push(1); // unit: meter
+        write_chunk(destination, b"pHYs", &physical)?;
+    }
+
+    write_chunk(
+        destination,
+        b"IDAT",
+        &zlib::compress(&filtered, compression),
+    )?;
+    write_chunk(destination, b"IEND", &[])?;
+
+    Ok(())
+}
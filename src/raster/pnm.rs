@@ -0,0 +1,172 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::Path;
+
+use super::{Pixels, Raster};
+
+use crate::error::{Error, Result};
+use crate::object::MAX_INITIAL_CAPACITY;
+
+fn invalid_data(message: &str) -> Error {
+    Error::InvalidImage(message.to_string())
+}
+
+/// Writes a binary PGM (`P5`) or PPM (`P6`) file; 16-bit samples are stored
+/// big-endian with a maximum value of 65535.
+pub fn to_writer<W: Write>(raster: &Raster, destination: &mut W) -> Result<()> {
+    let (magic, max_value) = match raster.pixels() {
+        Pixels::Gray8(_) => ("P5", u8::MAX as u32),
+        Pixels::Gray16(_) => ("P5", u16::MAX as u32),
+        Pixels::Rgb8(_) => ("P6", u8::MAX as u32),
+        Pixels::Rgb16(_) => ("P6", u16::MAX as u32),
+        Pixels::GrayF32(_) => {
+            return Err(Error::UnsupportedFormat(
+                "PNM cannot store floating point samples".to_string(),
+            ))
+        }
+    };
+
+    write!(
+        destination,
+        "{}\n{} {}\n{}\n",
+        magic,
+        raster.width(),
+        raster.height(),
+        max_value
+    )?;
+    destination.write_all(&raster.pixels().to_be_bytes())?;
+
+    Ok(())
+}
+
+/// Reads a PGM (`P2`, `P5`) or PPM (`P3`, `P6`) file. Samples are rescaled
+/// from the maximum value of the file to the full range of 8-bit samples, or
+/// of 16-bit samples when the maximum value exceeds 255.
+pub fn from_reader<R: BufRead>(source: &mut R) -> Result<Raster> {
+    let magic = read_token(source)?;
+    let (channels, ascii) = match magic.as_str() {
+        "P2" => (1, true),
+        "P3" => (3, true),
+        "P5" => (1, false),
+        "P6" => (3, false),
+        _ => {
+            return Err(Error::UnsupportedFormat(format!(
+                "PNM magic number `{}`",
+                magic
+            )))
+        }
+    };
+
+    let width = read_number(source)?;
+    let height = read_number(source)?;
+    let max_value = read_number(source)?;
+    if width == 0 || height == 0 {
+        return Err(invalid_data("invalid PNM dimensions"));
+    }
+    if max_value == 0 || max_value > u16::MAX as u32 {
+        return Err(invalid_data("invalid PNM maximum value"));
+    }
+
+    let count = (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|pixels| pixels.checked_mul(channels))
+        .ok_or(Error::DimensionOverflow { width, height })?;
+
+    let mut samples = Vec::with_capacity(count.min(MAX_INITIAL_CAPACITY));
+    if ascii {
+        for _ in 0..count {
+            samples.push(read_number(source)?);
+        }
+    } else {
+        // The single whitespace byte after the maximum value was consumed by
+        // `read_token`.
+        let size = if max_value > u8::MAX as u32 { 2 } else { 1 };
+        let length = count
+            .checked_mul(size)
+            .ok_or(Error::DimensionOverflow { width, height })?;
+        // The size in the header is not trusted with the allocation.
+        let mut bytes = Vec::with_capacity(length.min(MAX_INITIAL_CAPACITY));
+        source.take(length as u64).read_to_end(&mut bytes)?;
+        if bytes.len() < length {
+            return Err(invalid_data("truncated PNM data"));
+        }
+        samples.extend(bytes.chunks_exact(size).map(|sample| match sample {
+            [high, low] => u16::from_be_bytes([*high, *low]) as u32,
+            _ => sample[0] as u32,
+        }));
+    }
+    if samples.iter().any(|&sample| sample > max_value) {
+        return Err(invalid_data("PNM sample exceeds the maximum value"));
+    }
+
+    let eight_bit = || -> Vec<u8> {
+        rescale(&samples, max_value, u8::MAX as u32)
+            .map(|sample| sample as u8)
+            .collect()
+    };
+    let sixteen_bit = || -> Vec<u16> {
+        rescale(&samples, max_value, u16::MAX as u32)
+            .map(|sample| sample as u16)
+            .collect()
+    };
+    let pixels = match (max_value > u8::MAX as u32, channels) {
+        (false, 1) => Pixels::Gray8(eight_bit()),
+        (false, _) => Pixels::Rgb8(eight_bit()),
+        (true, 1) => Pixels::Gray16(sixteen_bit()),
+        (true, _) => Pixels::Rgb16(sixteen_bit()),
+    };
+
+    Ok(Raster::new(width, height, pixels))
+}
+
+pub fn open<P: AsRef<Path>>(path: P) -> Result<Raster> {
+    let path = path.as_ref();
+    File::open(path)
+        .map_err(Error::from)
+        .and_then(|file| from_reader(&mut BufReader::new(file)))
+        .map_err(|error| error.with_path(path))
+}
+
+fn rescale(samples: &[u32], from: u32, to: u32) -> impl Iterator<Item = u32> + '_ {
+    samples
+        .iter()
+        .map(move |&sample| (sample * to + from / 2) / from)
+}
+
+/// Reads the next whitespace-delimited header token, skipping `#` comments,
+/// and consumes the single whitespace byte that ends it.
+fn read_token<R: BufRead>(source: &mut R) -> Result<String> {
+    let mut token = Vec::new();
+    let mut comment = false;
+
+    loop {
+        let mut byte = [0];
+        if source.read(&mut byte)? == 0 {
+            break;
+        }
+        let byte = byte[0];
+
+        if comment {
+            comment = byte != b'\n' && byte != b'\r';
+        } else if byte == b'#' && token.is_empty() {
+            comment = true;
+        } else if byte.is_ascii_whitespace() {
+            if !token.is_empty() {
+                break;
+            }
+        } else {
+            token.push(byte);
+        }
+    }
+
+    match token.is_empty() {
+        true => Err(invalid_data("truncated PNM header")),
+        false => String::from_utf8(token).map_err(|_| invalid_data("invalid PNM header")),
+    }
+}
+
+fn read_number<R: BufRead>(source: &mut R) -> Result<u32> {
+    read_token(source)?
+        .parse()
+        .map_err(|_| invalid_data("invalid number in PNM file"))
+}
@@ -0,0 +1,107 @@
+use std::io::{self, Write};
+
+use super::{Pixels, Raster};
+
+use crate::error::{Error, Result};
+
+const TYPE_SHORT: u16 = 3;
+const TYPE_LONG: u16 = 4;
+const TYPE_RATIONAL: u16 = 5;
+
+const SAMPLE_FORMAT_UINT: u32 = 1;
+const SAMPLE_FORMAT_FLOAT: u32 = 3;
+
+struct Entry {
+    tag: u16,
+    kind: u16,
+    value: u32,
+}
+
+impl Entry {
+    fn new(tag: u16, kind: u16, value: u32) -> Self {
+        Entry { tag, kind, value }
+    }
+
+    fn to_writer<W: Write>(&self, destination: &mut W) -> io::Result<()> {
+        destination.write_all(&self.tag.to_le_bytes())?;
+        destination.write_all(&self.kind.to_le_bytes())?;
+        destination.write_all(&1u32.to_le_bytes())?;
+        match self.kind {
+            // SHORT values are left-justified within the 4-byte value field.
+            TYPE_SHORT => {
+                destination.write_all(&(self.value as u16).to_le_bytes())?;
+                destination.write_all(&[0; 2])?;
+            }
+            _ => destination.write_all(&self.value.to_le_bytes())?,
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes a single-strip, uncompressed, little-endian grayscale TIFF. This is
+/// mainly meant for 32-bit float phase maps, but 8 and 16-bit samples work as
+/// well.
+pub fn to_writer<W: Write>(raster: &Raster, destination: &mut W) -> Result<()> {
+    let (bits_per_sample, sample_format) = match raster.pixels() {
+        Pixels::Gray8(_) => (8, SAMPLE_FORMAT_UINT),
+        Pixels::Gray16(_) => (16, SAMPLE_FORMAT_UINT),
+        Pixels::GrayF32(_) => (32, SAMPLE_FORMAT_FLOAT),
+        Pixels::Rgb8(_) | Pixels::Rgb16(_) => {
+            return Err(Error::UnsupportedFormat(
+                "TIFF output only supports grayscale samples".to_string(),
+            ))
+        }
+    };
+
+    let data = raster.pixels().to_le_bytes();
+    let data_offset = 8u32;
+    // The IFD must start on a word boundary.
+    let ifd_offset = (data_offset + data.len() as u32 + 1) & !1;
+
+    let mut entries = vec![
+        Entry::new(256, TYPE_LONG, raster.width()),
+        Entry::new(257, TYPE_LONG, raster.height()),
+        Entry::new(258, TYPE_SHORT, bits_per_sample),
+        Entry::new(259, TYPE_SHORT, 1), // no compression
+        Entry::new(262, TYPE_SHORT, 1), // black is zero
+        Entry::new(273, TYPE_LONG, data_offset),
+        Entry::new(277, TYPE_SHORT, 1),
+        Entry::new(278, TYPE_LONG, raster.height()),
+        Entry::new(279, TYPE_LONG, data.len() as u32),
+    ];
+
+    // Resolution rationals are stored after the IFD.
+    let entry_count = entries.len() as u32 + 4;
+    let rational_offset = ifd_offset + 2 + entry_count * 12 + 4;
+    let (resolution, unit) = match raster.pixel_pitch() {
+        Some(pixel_pitch) => ((0.01 / pixel_pitch).round() as u32, 3), // centimeter
+        None => (1, 1),                                                // no absolute unit
+    };
+
+    entries.push(Entry::new(282, TYPE_RATIONAL, rational_offset));
+    entries.push(Entry::new(283, TYPE_RATIONAL, rational_offset + 8));
+    entries.push(Entry::new(296, TYPE_SHORT, unit));
+    entries.push(Entry::new(339, TYPE_SHORT, sample_format));
+
+    destination.write_all(b"II")?;
+    destination.write_all(&42u16.to_le_bytes())?;
+    destination.write_all(&ifd_offset.to_le_bytes())?;
+    destination.write_all(&data)?;
+    if data.len() % 2 == 1 {
+        destination.write_all(&[0])?;
+    }
+
+    destination.write_all(&(entries.len() as u16).to_le_bytes())?;
+    for entry in &entries {
+        entry.to_writer(destination)?;
+    }
+    destination.write_all(&0u32.to_le_bytes())?; // no further IFDs
+
+    for _ in 0..2 {
+        destination.write_all(&resolution.to_le_bytes())?;
+        destination.write_all(&1u32.to_le_bytes())?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,74 @@
+//! Numerical reconstruction of phase holograms.
+//!
+//! The hologram is modelled as a phase-only SLM lit by a unit plane wave, so
+//! its field is `exp(iφ)`. Propagating that field back by the depth of an
+//! object point brings the point into focus.
+
+use ndarray::Array2;
+use num::Complex;
+use std::f64::consts::PI;
+
+use crate::bmp::Image;
+use crate::cgh::CghConfig;
+use crate::error::Result;
+use crate::propagation::{Method, Propagator};
+
+#[derive(Debug, Clone, Copy)]
+pub struct Reconstruction {
+    pub propagator: Propagator,
+    pub method: Method,
+}
+
+impl Reconstruction {
+    /// Reconstruction with the pixel pitch and wavelength of `config`, using
+    /// the angular spectrum method.
+    pub fn new(config: &CghConfig) -> Self {
+        Reconstruction {
+            propagator: Propagator::new(config.pixel_pitch, config.wavelength),
+            method: Method::AngularSpectrum,
+        }
+    }
+
+    /// Complex field at `depth` meters in front of the SLM.
+    pub fn field(&self, phase: &Array2<f64>, depth: f64) -> Array2<Complex<f64>> {
+        self.propagator
+            .propagate(&slm_field(phase), -depth, self.method)
+    }
+
+    /// Intensity at `depth` meters in front of the SLM.
+    pub fn intensity(&self, phase: &Array2<f64>, depth: f64) -> Array2<f64> {
+        self.field(phase, depth).mapv(|complex| complex.norm_sqr())
+    }
+}
+
+/// The field `exp(iφ)` of a phase-only SLM showing `phase` (in radians).
+pub fn slm_field(phase: &Array2<f64>) -> Array2<Complex<f64>> {
+    phase.mapv(|phi| Complex::from_polar(1.0, phi))
+}
+
+/// Phase map of a hologram read back from an 8-bit image, mapping the gray
+/// levels 0..256 linearly onto 0..2π.
+pub fn phase_from_image(image: &Image) -> Array2<f64> {
+    let (width, height) = (image.width() as usize, image.height() as usize);
+
+    Array2::from_shape_fn((height, width), |(y, x)| {
+        2.0 * PI * image.get_pixel(x as u32, y as u32) as f64 / 256.0
+    })
+}
+
+/// Converts an intensity map to an 8-bit image, scaled so that the brightest
+/// pixel becomes 255.
+pub fn intensity_image(intensity: &Array2<f64>, pixel_pitch: f64) -> Result<Image> {
+    let (height, width) = intensity.dim();
+    let max = intensity.iter().copied().fold(0.0, f64::max);
+    let scale = if max > 0.0 { 255.0 / max } else { 0.0 };
+
+    let mut image = Image::new(width as u32, height as u32)?;
+    image.set_pixel_pitch(pixel_pitch);
+
+    for ((y, x), &pixel) in intensity.indexed_iter() {
+        image.set_pixel(x as u32, y as u32, (pixel * scale).round() as u8);
+    }
+
+    Ok(image)
+}
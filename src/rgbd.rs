@@ -0,0 +1,178 @@
+//! RGB-D input: an intensity or color image with a depth map of the same size,
+//! as captured by depth cameras.
+//!
+//! Both images can be BMP or PNM files. Depth values are normalized to `0..=1`
+//! from the sample range of the file, with 0 the nearest depth.
+
+use ndarray::Array2;
+use std::path::Path;
+
+use crate::error::{Error, Result};
+use crate::object::Object3d;
+use crate::point::Point;
+use crate::raster::{ImageFormat, Pixels, Raster};
+
+#[derive(Debug, Clone)]
+pub struct RgbdImage {
+    /// Intensity in `0..=1`.
+    intensity: Array2<f64>,
+    /// Red, green and blue intensities in `0..=1`, for color inputs.
+    colors: Option<Array2<[f64; 3]>>,
+    /// Normalized depth in `0..=1`.
+    depth: Array2<f64>,
+}
+
+impl RgbdImage {
+    pub fn new(intensity: Array2<f64>, depth: Array2<f64>) -> Result<Self> {
+        if intensity.dim() != depth.dim() {
+            return Err(Error::InvalidImage(format!(
+                "depth map of {}x{} pixels for an image of {}x{}",
+                depth.ncols(),
+                depth.nrows(),
+                intensity.ncols(),
+                intensity.nrows()
+            )));
+        }
+
+        Ok(RgbdImage {
+            intensity,
+            colors: None,
+            depth,
+        })
+    }
+
+    /// Builds the input from a gray or RGB raster and a gray depth map. The
+    /// intensity of RGB rasters is their luminance.
+    pub fn from_rasters(image: &Raster, depth: &Raster) -> Result<Self> {
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        let shape = |samples: Vec<f64>, width: usize, height: usize| {
+            Array2::from_shape_vec((height, width), samples).unwrap()
+        };
+
+        let (intensity, colors) = match image.pixels() {
+            Pixels::Gray8(_) | Pixels::Gray16(_) | Pixels::GrayF32(_) => {
+                (shape(gray_samples(image.pixels()), width, height), None)
+            }
+            Pixels::Rgb8(_) | Pixels::Rgb16(_) => {
+                let samples = rgb_samples(image.pixels());
+                let luminance = samples
+                    .iter()
+                    .map(|[r, g, b]| 0.299 * r + 0.587 * g + 0.114 * b)
+                    .collect();
+                let colors = Array2::from_shape_vec((height, width), samples).unwrap();
+                (shape(luminance, width, height), Some(colors))
+            }
+        };
+
+        if depth.pixels().channels() != 1 {
+            return Err(Error::InvalidImage(
+                "depth map must be grayscale".to_string(),
+            ));
+        }
+        let depth = shape(
+            gray_samples(depth.pixels()),
+            depth.width() as usize,
+            depth.height() as usize,
+        );
+
+        let rgbd = RgbdImage::new(intensity, depth)?;
+        Ok(RgbdImage { colors, ..rgbd })
+    }
+
+    /// Opens an image and its depth map, each a BMP (read as grayscale) or
+    /// PNM file chosen by extension.
+    pub fn open<P: AsRef<Path>, Q: AsRef<Path>>(image: P, depth: Q) -> Result<Self> {
+        Self::from_rasters(&open_raster(image.as_ref())?, &open_raster(depth.as_ref())?)
+    }
+
+    pub fn width(&self) -> usize {
+        self.intensity.ncols()
+    }
+
+    pub fn height(&self) -> usize {
+        self.intensity.nrows()
+    }
+
+    pub fn intensity(&self) -> &Array2<f64> {
+        &self.intensity
+    }
+
+    pub fn colors(&self) -> Option<&Array2<[f64; 3]>> {
+        self.colors.as_ref()
+    }
+
+    pub fn depth(&self) -> &Array2<f64> {
+        &self.depth
+    }
+
+    /// One point per lit pixel, centered on the middle of the image in pixel
+    /// units, at depth `depth * depth_range`. Points are placed by
+    /// `CghConfig::offset` and `scalar` like any other object. Amplitudes (and
+    /// colors) are the square roots of the intensities.
+    pub fn to_object(&self, depth_range: f64) -> Object3d {
+        let (center_x, center_y) = (self.width() as f64 / 2.0, self.height() as f64 / 2.0);
+        let lit = || {
+            self.intensity
+                .indexed_iter()
+                .filter(|(_, &intensity)| intensity > 0.0)
+        };
+
+        let points = lit()
+            .map(|((y, x), _)| {
+                Point::new(
+                    x as f64 - center_x,
+                    y as f64 - center_y,
+                    self.depth[(y, x)] * depth_range,
+                )
+            })
+            .collect();
+        let amplitudes = lit().map(|(_, intensity)| intensity.sqrt()).collect();
+        let object = Object3d::new(points).with_amplitudes(amplitudes);
+
+        match &self.colors {
+            Some(colors) => {
+                let colors = lit()
+                    .map(|(index, _)| colors[index].map(|c| c.max(0.0).sqrt()))
+                    .collect();
+                object.with_colors(colors)
+            }
+            None => object,
+        }
+    }
+}
+
+fn open_raster(path: &Path) -> Result<Raster> {
+    match ImageFormat::from_path(path) {
+        Some(format) => Raster::open(path, format),
+        None => Err(Error::UnsupportedFormat(format!(
+            "cannot read {} as an RGB-D image",
+            path.display()
+        ))
+        .with_path(path)),
+    }
+}
+
+/// Gray samples scaled to `0..=1`.
+fn gray_samples(pixels: &Pixels) -> Vec<f64> {
+    match pixels {
+        Pixels::Gray8(samples) => samples.iter().map(|&v| v as f64 / 255.0).collect(),
+        Pixels::Gray16(samples) => samples.iter().map(|&v| v as f64 / 65535.0).collect(),
+        Pixels::GrayF32(samples) => samples.iter().map(|&v| v as f64).collect(),
+        Pixels::Rgb8(_) | Pixels::Rgb16(_) => unreachable!("not a gray raster"),
+    }
+}
+
+/// RGB samples scaled to `0..=1`.
+fn rgb_samples(pixels: &Pixels) -> Vec<[f64; 3]> {
+    match pixels {
+        Pixels::Rgb8(samples) => samples
+            .chunks_exact(3)
+            .map(|rgb| [0, 1, 2].map(|i| rgb[i] as f64 / 255.0))
+            .collect(),
+        Pixels::Rgb16(samples) => samples
+            .chunks_exact(3)
+            .map(|rgb| [0, 1, 2].map(|i| rgb[i] as f64 / 65535.0))
+            .collect(),
+        _ => unreachable!("not an RGB raster"),
+    }
+}
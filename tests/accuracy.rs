@@ -0,0 +1,459 @@
+//! Accuracy of the CGH processors on small synthetic objects (see `common`),
+//! against reference fields computed directly in this suite.
+//!
+//! Each comparison reports the phase RMS error of the object field and the
+//! PSNR of its reconstruction at the object depth (run with `--nocapture` to
+//! see them) and checks them against these tolerances:
+//!
+//! | processor               | reference        | phase RMS         | PSNR   |
+//! |-------------------------|------------------|-------------------|--------|
+//! | naive                   | spherical        | 1e-9 rad          | 200 dB |
+//! | fresnel                 | paraxial         | 1e-9 rad          | 200 dB |
+//! | fresnel                 | spherical        | 5e-3 rad          | 80 dB  |
+//! | recurrence              | paraxial         | 1e-8 rad          | 200 dB |
+//! | simd (every kernel)     | spherical        | 1e-9 rad          | 200 dB |
+//! | wrp                     | spherical        | 0.15 rad          | 45 dB  |
+//! | layer (pixel-aligned)   | spherical        | 0.15 rad          | 55 dB  |
+//! | polygon (one triangle)  | sampled triangle | -                 | 30 dB  |
+//! | naive with `TriFuncLut` | naive            | 1.2 step / √12    | 90 dB  |
+//! | same, interpolating     | naive            | see `LutMode`     | 90 dB  |
+//!
+//! Parallel variants must give the same field as their serial processor, and
+//! the SIMD kernels the same field as each other.
+//! `TriFuncLut` truncates the phase to its step `2π / SIZE` by default, an
+//! error uniform in `(-step, 0]`. Its mean is removed with the constant phase
+//! offset, which leaves an RMS of `step / √12`. The interpolating modes must
+//! stay within the largest errors documented on `LutMode`.
+//!
+//! The PSNR is high throughout because the reconstructions are mostly dark
+//! around a few bright points.
+
+mod common;
+
+use ndarray::Array2;
+use num::Complex;
+use std::f64::consts::PI;
+
+use holography::cgh::fresnel::{CghProcessorFresnel, CghProcessorFresnelParallel};
+use holography::cgh::layer::CghProcessorLayer;
+use holography::cgh::naive::{CghProcessorNaive, CghProcessorNaiveParallel};
+use holography::cgh::polygon::CghProcessorPolygon;
+use holography::cgh::recurrence::{CghProcessorRecurrence, CghProcessorRecurrenceParallel};
+use holography::cgh::simd::{CghProcessorSimd, CghProcessorSimdParallel, Kernel};
+use holography::cgh::wrp::CghProcessorWrp;
+use holography::cgh::{CghConfig, CghProcessor};
+use holography::lut::{LutMode, TriFunc, TriFuncLut, TriFuncNaive, TrigError};
+use holography::object::Object3d;
+use holography::point::Point;
+
+use common::*;
+
+/// Compares `field` of `object` with `reference` and returns the phase RMS
+/// error and reconstruction PSNR, after printing them.
+fn compare(
+    label: &str,
+    name: &str,
+    config: &CghConfig,
+    field: &Array2<Complex<f64>>,
+    reference: &Array2<Complex<f64>>,
+) -> (f64, f64) {
+    let error = phase_rms(field, reference);
+    let psnr = psnr(config, field, reference, depth());
+    eprintln!(
+        "{:<28} {:<14} phase RMS {:.3e} rad, PSNR {:.1} dB",
+        label, name, error, psnr
+    );
+
+    (error, psnr)
+}
+
+fn check(
+    label: &str,
+    processor: &dyn CghProcessor,
+    reference: fn(&CghConfig, &Object3d) -> Array2<Complex<f64>>,
+    max_error: f64,
+    min_psnr: f64,
+) {
+    let config = config();
+    for (name, object) in objects() {
+        let field = processor.field(&TriFuncNaive, &config, &object);
+        let expected = reference(&config, &object);
+        let (error, psnr) = compare(label, name, &config, &field, &expected);
+
+        assert!(
+            error <= max_error,
+            "{} on {}: phase RMS {:e} rad above {:e}",
+            label,
+            name,
+            error,
+            max_error
+        );
+        assert!(
+            psnr >= min_psnr,
+            "{} on {}: PSNR {:.1} dB below {:.1}",
+            label,
+            name,
+            psnr,
+            min_psnr
+        );
+    }
+}
+
+fn check_same(label: &str, processor: &dyn CghProcessor, serial: &dyn CghProcessor) {
+    let config = config();
+    for (name, object) in objects() {
+        let field = processor.field(&TriFuncNaive, &config, &object);
+        let expected = serial.field(&TriFuncNaive, &config, &object);
+        let difference = max_difference(&field, &expected);
+
+        assert!(
+            difference <= 1e-12,
+            "{} on {}: differs from the serial processor by {:e}",
+            label,
+            name,
+            difference
+        );
+    }
+}
+
+#[test]
+fn naive_matches_spherical_reference() {
+    check(
+        "naive",
+        &CghProcessorNaive::default(),
+        spherical_reference,
+        1e-9,
+        200.0,
+    );
+}
+
+#[test]
+fn naive_parallel_matches_naive() {
+    check_same(
+        "naive-parallel",
+        &CghProcessorNaiveParallel::default(),
+        &CghProcessorNaive::default(),
+    );
+}
+
+#[test]
+fn fresnel_matches_paraxial_reference() {
+    check(
+        "fresnel",
+        &CghProcessorFresnel::default(),
+        paraxial_reference,
+        1e-9,
+        200.0,
+    );
+}
+
+#[test]
+fn fresnel_approximates_spherical_reference() {
+    check(
+        "fresnel vs spherical",
+        &CghProcessorFresnel::default(),
+        spherical_reference,
+        5e-3,
+        80.0,
+    );
+}
+
+#[test]
+fn fresnel_parallel_matches_fresnel() {
+    check_same(
+        "fresnel-parallel",
+        &CghProcessorFresnelParallel::default(),
+        &CghProcessorFresnel::default(),
+    );
+}
+
+#[test]
+fn recurrence_matches_paraxial_reference() {
+    check(
+        "recurrence",
+        &CghProcessorRecurrence,
+        paraxial_reference,
+        1e-8,
+        200.0,
+    );
+}
+
+#[test]
+fn recurrence_parallel_matches_recurrence() {
+    check_same(
+        "recurrence-parallel",
+        &CghProcessorRecurrenceParallel,
+        &CghProcessorRecurrence,
+    );
+}
+
+#[test]
+fn simd_matches_spherical_reference() {
+    for kernel in [Kernel::Scalar, Kernel::Avx2] {
+        if !kernel.is_supported() {
+            eprintln!(
+                "skipping the {:?} kernel, not supported by this CPU",
+                kernel
+            );
+            continue;
+        }
+        let label = format!("simd ({:?})", kernel);
+        check(
+            &label,
+            &CghProcessorSimd { kernel },
+            spherical_reference,
+            1e-9,
+            200.0,
+        );
+    }
+}
+
+#[test]
+fn simd_parallel_matches_simd() {
+    check_same(
+        "simd-parallel",
+        &CghProcessorSimdParallel::default(),
+        &CghProcessorSimd::default(),
+    );
+}
+
+/// The kernels sum the points in the same order, but the fused
+/// multiply-adds of AVX2 round the phases (about 10⁵ cycles here)
+/// differently, by up to about 1e-10 rad.
+#[test]
+fn simd_kernels_agree() {
+    if !Kernel::Avx2.is_supported() {
+        eprintln!("skipping, AVX2 is not supported by this CPU");
+        return;
+    }
+    let config = config();
+    for (name, object) in objects() {
+        let avx2 = CghProcessorSimd {
+            kernel: Kernel::Avx2,
+        };
+        let scalar = CghProcessorSimd {
+            kernel: Kernel::Scalar,
+        };
+        let field = avx2.field(&TriFuncNaive, &config, &object);
+        let expected = scalar.field(&TriFuncNaive, &config, &object);
+        let difference = max_difference(&field, &expected);
+
+        assert!(
+            difference <= 1e-9,
+            "simd on {}: the kernels differ by {:e}",
+            name,
+            difference
+        );
+    }
+}
+
+#[test]
+fn wrp_approximates_spherical_reference() {
+    check(
+        "wrp",
+        &CghProcessorWrp::default(),
+        spherical_reference,
+        0.15,
+        45.0,
+    );
+}
+
+/// The synthetic objects have integer coordinates, so with `SCALE` and the
+/// offset their points fall on pixel centers and the layer method only moves
+/// them to the middle of their depth slices.
+#[test]
+fn layer_approximates_spherical_reference() {
+    check(
+        "layer",
+        &CghProcessorLayer::default(),
+        spherical_reference,
+        0.15,
+        55.0,
+    );
+}
+
+/// A triangle facing the hologram (counterclockwise as seen from it) against
+/// the naive field of points covering it half a pixel apart. The models differ
+/// (a continuous surface against discrete points, with Lambertian shading), so
+/// only the reconstructions are compared.
+#[test]
+fn polygon_approximates_sampled_triangle() {
+    let config = config();
+    let triangle = Object3d::new(vec![
+        Point::new(-4.0, -4.0, 0.0),
+        Point::new(4.0, -4.0, 0.0),
+        Point::new(0.0, 4.0, 0.0),
+    ])
+    .with_faces(vec![[0, 2, 1]]);
+    let sampled = triangle.sample_faces(0.5 / SCALE);
+
+    let field = CghProcessorPolygon::default().field(&TriFuncNaive, &config, &triangle);
+    let expected = spherical_reference(&config, &sampled);
+    let (_, psnr) = compare("polygon", "triangle", &config, &field, &expected);
+
+    assert!(psnr >= 30.0, "polygon: PSNR {:.1} dB below 30", psnr);
+}
+
+fn check_lut<const SIZE: usize>(mode: LutMode, tolerance: f64) {
+    let config = config();
+    let processor = CghProcessorNaive::default();
+    let lut = TriFuncLut::<SIZE>::with_mode(mode);
+
+    for (name, object) in objects() {
+        let field = processor.field(&lut, &config, &object);
+        let expected = processor.field(&TriFuncNaive, &config, &object);
+        let label = format!("naive with lut({}, {:?})", SIZE, mode);
+        let (error, psnr) = compare(&label, name, &config, &field, &expected);
+
+        assert!(
+            error <= tolerance,
+            "{} on {}: phase RMS {:e} rad above {:e}",
+            label,
+            name,
+            error,
+            tolerance
+        );
+        assert!(
+            psnr >= 90.0,
+            "{} on {}: PSNR {:.1} dB below 90",
+            label,
+            name,
+            psnr
+        );
+    }
+}
+
+/// The phase RMS of a truncating or rounding table, `step / √12`, with some
+/// margin.
+fn rounding_tolerance(size: usize) -> f64 {
+    1.2 * (2.0 * PI / size as f64) / 12f64.sqrt()
+}
+
+#[test]
+fn lut_error_scales_with_step() {
+    check_lut::<256>(LutMode::Truncate, rounding_tolerance(256));
+    check_lut::<4096>(LutMode::Truncate, rounding_tolerance(4096));
+    check_lut::<16384>(LutMode::Truncate, rounding_tolerance(16384));
+    check_lut::<65536>(LutMode::Truncate, rounding_tolerance(65536));
+}
+
+#[test]
+fn lut_interpolation_reduces_error() {
+    let step = 2.0 * PI / 256.0;
+    check_lut::<256>(LutMode::Round, rounding_tolerance(256));
+    check_lut::<256>(LutMode::Linear, step.powi(2) / 8.0);
+    check_lut::<256>(LutMode::Quadratic, step.powi(3) / 16.0);
+}
+
+/// The largest errors of every mode are as documented on `LutMode`, within a
+/// few percent, and `sincos` agrees with `sin` and `cos`.
+#[test]
+fn lut_error_bounds() {
+    fn check<const SIZE: usize>() {
+        let step = 2.0 * PI / SIZE as f64;
+        let bounds = [step, step / 2.0, step.powi(2) / 8.0, step.powi(3) / 16.0];
+
+        for (mode, bound) in LutMode::ALL.into_iter().zip(bounds) {
+            let lut = TriFuncLut::<SIZE>::with_mode(mode);
+            let error = TrigError::measure(&lut, 100_000);
+            eprintln!("lut({}, {:?}): {:?}", SIZE, mode, error);
+
+            assert!(
+                error.max <= 1.01 * bound && error.max >= 0.9 * bound,
+                "lut({}, {:?}): max error {:e}, expected about {:e}",
+                SIZE,
+                mode,
+                error.max,
+                bound
+            );
+            assert!(error.rms < error.max);
+            for rad in [0.0, 0.3, 0.999, -0.25, 1.5, -1e-20] {
+                assert_eq!(lut.sincos(rad), (lut.sin(rad), lut.cos(rad)));
+            }
+        }
+    }
+
+    check::<256>();
+    check::<4096>();
+}
+
+/// The truncating `TriFuncLut` always rounds the phase down: its mean error is
+/// half a step.
+#[test]
+fn lut_truncates_phase() {
+    let lut = TriFuncLut::<256>::new();
+    let step = 1.0 / 256.0;
+    let samples = 10_000;
+
+    let bias = (0..samples)
+        .map(|i| {
+            let cycles = i as f64 / samples as f64;
+            let phase = lut.sin(cycles).atan2(lut.cos(cycles)) / (2.0 * PI);
+            let error = (phase - cycles + 0.5).rem_euclid(1.0) - 0.5;
+            assert!(error <= 1e-12 && error > -step - 1e-12);
+            error
+        })
+        .sum::<f64>()
+        / samples as f64;
+
+    assert!((bias + step / 2.0).abs() < step / 20.0, "bias {:e}", bias);
+}
+
+/// Rounding to the nearest entry keeps the phase within half a step, without
+/// bias, also for negative phases.
+#[test]
+fn lut_rounds_phase() {
+    let lut = TriFuncLut::<256>::with_mode(LutMode::Round);
+    let step = 1.0 / 256.0;
+    let samples = 10_000;
+
+    let bias = (0..samples)
+        .map(|i| {
+            let cycles = i as f64 / samples as f64 - 0.5;
+            let phase = lut.sin(cycles).atan2(lut.cos(cycles)) / (2.0 * PI);
+            let error = (phase - cycles + 0.5).rem_euclid(1.0) - 0.5;
+            assert!(error.abs() <= step / 2.0 + 1e-12);
+            error
+        })
+        .sum::<f64>()
+        / samples as f64;
+
+    assert!(bias.abs() < step / 20.0, "bias {:e}", bias);
+}
+
+/// `eval_batch` gives the same values as `sincos`, for the overriding
+/// implementations and for the default one.
+#[test]
+fn batch_matches_sincos() {
+    struct Unbatched;
+
+    impl TriFunc for Unbatched {
+        fn sin(&self, rad: f64) -> f64 {
+            (2.0 * PI * rad).sin()
+        }
+
+        fn cos(&self, rad: f64) -> f64 {
+            (2.0 * PI * rad).cos()
+        }
+    }
+
+    let phases: Vec<_> = (0..1000).map(|i| i as f64 * 0.0137 - 5.0).collect();
+    let mut tri_funcs: Vec<(String, Box<dyn TriFunc>)> = vec![
+        ("naive".to_string(), Box::new(TriFuncNaive)),
+        ("unbatched".to_string(), Box::new(Unbatched)),
+    ];
+    for mode in LutMode::ALL {
+        let label = format!("lut(1024, {:?})", mode);
+        tri_funcs.push((label, Box::new(TriFuncLut::<1024>::with_mode(mode))));
+    }
+
+    for (label, tri_func) in tri_funcs {
+        let mut batch = vec![Complex::default(); phases.len()];
+        tri_func.eval_batch(&phases, &mut batch);
+
+        for (&rad, value) in phases.iter().zip(batch) {
+            let (sin, cos) = tri_func.sincos(rad);
+            assert_eq!(value, Complex::new(cos, sin), "{} at {}", label, rad);
+        }
+    }
+}
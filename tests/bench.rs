@@ -0,0 +1,67 @@
+//! The benchmark matrix.
+
+mod common;
+
+use holography::bench::{point_cloud, Matrix, Measurement};
+use holography::error::Error;
+use holography::job::{ProcessorKind, Settings, Trig};
+
+use common::*;
+
+fn matrix(processors: Vec<ProcessorKind>, threads: Vec<usize>) -> Matrix {
+    Matrix {
+        processors,
+        settings: Settings::default(),
+        trigs: vec![Trig::default(), "lut:1024".parse().unwrap()],
+        objects: vec![("cloud".to_string(), point_cloud(4, 0))],
+        resolutions: vec![(32, 16)],
+        threads,
+        repeat: 1,
+        config: config(),
+        accuracy: false,
+    }
+}
+
+fn run(matrix: &Matrix) -> Vec<Measurement> {
+    matrix.run(|_| {}).unwrap()
+}
+
+#[test]
+fn thread_counts_are_required() {
+    for threads in [vec![], vec![1, 0]] {
+        let matrix = matrix(vec![ProcessorKind::NaiveParallel], threads);
+        assert!(matches!(matrix.run(|_| {}), Err(Error::InvalidBench(_))));
+    }
+}
+
+/// The fewest threads are the base of the efficiency, whatever their place
+/// in the list.
+#[test]
+fn efficiency_is_relative_to_fewest_threads() {
+    let measurements = run(&matrix(vec![ProcessorKind::NaiveParallel], vec![2, 1]));
+
+    assert_eq!(measurements.len(), 4);
+    for measurement in &measurements {
+        if measurement.threads == 1 {
+            assert_eq!(measurement.efficiency, 1.0);
+        }
+    }
+}
+
+#[test]
+fn trig_axis_collapses_for_processors_without_trig() {
+    let measurements = run(&matrix(
+        vec![ProcessorKind::Simd, ProcessorKind::Naive],
+        vec![1],
+    ));
+    let trigs = |processor: &str| {
+        measurements
+            .iter()
+            .filter(|m| m.processor == processor)
+            .map(|m| m.trig.as_str())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(trigs("simd"), ["-"]);
+    assert_eq!(trigs("naive"), ["naive", "lut:1024"]);
+}
@@ -0,0 +1,231 @@
+//! Byte layout of the BMP writer against hand-assembled files: little-endian
+//! headers, the gray color table, bottom-up rows padded to 4 bytes, packed 1
+//! and 4-bit pixels and BGR order for 24-bit images. Also the errors of the
+//! reader on malformed files.
+
+use holography::bmp::{Image, RgbImage};
+use holography::error::Error;
+
+fn written(write: impl FnOnce(&mut Vec<u8>) -> std::io::Result<()>) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    write(&mut bytes).unwrap();
+    bytes
+}
+
+/// `BITMAPFILEHEADER` followed by a `BITMAPINFOHEADER`.
+#[allow(clippy::too_many_arguments)]
+fn headers(
+    file_size: u32,
+    data_offset: u32,
+    width: i32,
+    height: i32,
+    bits_per_pixel: u16,
+    image_size: u32,
+    pixels_per_meter: i32,
+    colors_used: u32,
+) -> Vec<u8> {
+    let mut bytes = b"BM".to_vec();
+    bytes.extend(file_size.to_le_bytes());
+    bytes.extend([0; 4]);
+    bytes.extend(data_offset.to_le_bytes());
+
+    bytes.extend(40u32.to_le_bytes());
+    bytes.extend(width.to_le_bytes());
+    bytes.extend(height.to_le_bytes());
+    bytes.extend(1u16.to_le_bytes());
+    bytes.extend(bits_per_pixel.to_le_bytes());
+    bytes.extend(0u32.to_le_bytes());
+    bytes.extend(image_size.to_le_bytes());
+    bytes.extend(pixels_per_meter.to_le_bytes());
+    bytes.extend(pixels_per_meter.to_le_bytes());
+    bytes.extend(colors_used.to_le_bytes());
+    bytes.extend(0u32.to_le_bytes());
+    bytes
+}
+
+fn gray_table(levels: impl IntoIterator<Item = u8>) -> Vec<u8> {
+    levels.into_iter().flat_map(|i| [i, i, i, 0]).collect()
+}
+
+#[test]
+fn headers_are_little_endian() {
+    // A 3x2, 8-bit image with a pitch of 8μm (125000 pixels per meter).
+    let expected: [u8; 54] = [
+        0x42, 0x4d, // "BM"
+        0x3e, 0x04, 0x00, 0x00, // file size: 1078 + 8
+        0x00, 0x00, 0x00, 0x00, // reserved
+        0x36, 0x04, 0x00, 0x00, // data offset: 14 + 40 + 256 * 4
+        0x28, 0x00, 0x00, 0x00, // info header size
+        0x03, 0x00, 0x00, 0x00, // width
+        0x02, 0x00, 0x00, 0x00, // height, positive for bottom-up rows
+        0x01, 0x00, // planes
+        0x08, 0x00, // bits per pixel
+        0x00, 0x00, 0x00, 0x00, // no compression
+        0x08, 0x00, 0x00, 0x00, // image size: 2 rows of 4 bytes
+        0x48, 0xe8, 0x01, 0x00, // x pixels per meter
+        0x48, 0xe8, 0x01, 0x00, // y pixels per meter
+        0x00, 0x00, 0x00, 0x00, // colors used: all 256
+        0x00, 0x00, 0x00, 0x00, // important colors
+    ];
+
+    let mut image = Image::new(3, 2).unwrap();
+    image.set_pixel_pitch(8.0e-6);
+    let bytes = written(|bytes| image.to_writer(bytes));
+
+    assert_eq!(bytes[..54], expected);
+}
+
+#[test]
+fn eight_bit_layout() {
+    let mut image = Image::new(3, 2).unwrap();
+    image.set_pixel_pitch(8.0e-6);
+    for (i, (x, y)) in [(0, 0), (1, 0), (2, 0), (0, 1), (1, 1), (2, 1)]
+        .into_iter()
+        .enumerate()
+    {
+        image.set_pixel(x, y, i as u8 + 1);
+    }
+
+    let mut expected = headers(1086, 1078, 3, 2, 8, 8, 125000, 0);
+    expected.extend(gray_table(0..=255));
+    // The bottom row first, each padded to 4 bytes.
+    expected.extend([4, 5, 6, 0]);
+    expected.extend([1, 2, 3, 0]);
+
+    assert_eq!(written(|bytes| image.to_writer(bytes)), expected);
+}
+
+#[test]
+fn one_bit_layout() {
+    let mut image = Image::new(10, 1).unwrap();
+    for (x, level) in [255, 0, 255, 0, 255, 0, 255, 0, 255, 128]
+        .into_iter()
+        .enumerate()
+    {
+        image.set_pixel(x as u32, 0, level);
+    }
+    image.set_bits_per_pixel(1).unwrap();
+
+    let mut expected = headers(66, 62, 10, 1, 1, 4, 0, 2);
+    expected.extend(gray_table([0, 255]));
+    // Leftmost pixel in the most significant bit, 128 has its top bit set.
+    expected.extend([0b1010_1010, 0b1100_0000, 0, 0]);
+
+    assert_eq!(written(|bytes| image.to_writer(bytes)), expected);
+}
+
+#[test]
+fn four_bit_layout() {
+    let mut image = Image::new(3, 1).unwrap();
+    for (x, level) in [0x10, 0xff, 0x80].into_iter().enumerate() {
+        image.set_pixel(x as u32, 0, level);
+    }
+    image.set_bits_per_pixel(4).unwrap();
+
+    let mut expected = headers(122, 118, 3, 1, 4, 4, 0, 16);
+    expected.extend(gray_table((0..16).map(|i| i * 17)));
+    expected.extend([0x1f, 0x80, 0, 0]);
+
+    assert_eq!(written(|bytes| image.to_writer(bytes)), expected);
+}
+
+#[test]
+fn rgb_layout() {
+    let mut image = RgbImage::new(2, 2).unwrap();
+    image.set_pixel(0, 0, [1, 2, 3]);
+    image.set_pixel(1, 0, [4, 5, 6]);
+    image.set_pixel(0, 1, [7, 8, 9]);
+    image.set_pixel(1, 1, [10, 11, 12]);
+
+    let mut expected = headers(70, 54, 2, 2, 24, 16, 0, 0);
+    // BGR, bottom row first, rows of 6 bytes padded to 8.
+    expected.extend([9, 8, 7, 12, 11, 10, 0, 0]);
+    expected.extend([3, 2, 1, 6, 5, 4, 0, 0]);
+
+    assert_eq!(written(|bytes| image.to_writer(bytes)), expected);
+}
+
+#[test]
+fn written_images_read_back() {
+    let mut image = Image::new(5, 3).unwrap();
+    image.set_pixel_pitch(8.0e-6);
+    for (x, y) in image.coordinates().collect::<Vec<_>>() {
+        image.set_pixel(x, y, (x * 40 + y * 7) as u8);
+    }
+    let bytes = written(|bytes| image.to_writer(bytes));
+
+    let read = Image::from_reader(&mut &bytes[..]).unwrap();
+    assert_eq!((read.width(), read.height()), (5, 3));
+    assert_eq!(read.bits_per_pixel(), 8);
+    assert_eq!(read.pixel_pitch(), Some(8.0e-6));
+    for (x, y) in image.coordinates() {
+        assert_eq!(
+            read.get_pixel(x, y),
+            image.get_pixel(x, y),
+            "pixel ({}, {})",
+            x,
+            y
+        );
+    }
+}
+
+#[test]
+fn malformed_files_are_rejected() {
+    let image = Image::new(3, 2).unwrap();
+    let valid = written(|bytes| image.to_writer(bytes));
+
+    let mut truncated = valid.clone();
+    truncated.truncate(valid.len() - 2);
+    let mut not_bmp = valid.clone();
+    not_bmp[..2].copy_from_slice(b"PK");
+    // Run-length encoded.
+    let mut compressed = valid.clone();
+    compressed[30] = 1;
+    // A 16-bit image.
+    let mut sixteen_bit = valid;
+    sixteen_bit[28] = 16;
+
+    assert!(matches!(
+        Image::from_reader(&mut &truncated[..]),
+        Err(Error::Io(_))
+    ));
+    assert!(matches!(
+        Image::from_reader(&mut &not_bmp[..]),
+        Err(Error::InvalidImage(_))
+    ));
+    for bytes in [compressed, sixteen_bit] {
+        assert!(matches!(
+            Image::from_reader(&mut &bytes[..]),
+            Err(Error::UnsupportedFormat(_))
+        ));
+    }
+}
+
+#[test]
+fn unsupported_depths_are_errors() {
+    let mut image = Image::new(3, 1).unwrap();
+    for bits_per_pixel in [0, 2, 16, 24] {
+        assert!(matches!(
+            image.set_bits_per_pixel(bits_per_pixel),
+            Err(Error::UnsupportedFormat(_))
+        ));
+    }
+    assert_eq!(image.bits_per_pixel(), 8);
+}
+
+/// Dimensions beyond the size of the file are rejected before the image is
+/// allocated.
+#[test]
+fn oversized_dimensions_are_rejected() {
+    for (file_size, width, height) in [
+        (54, 30000, 30000),
+        (54, 1, 2),
+        (u32::MAX, i32::MAX, i32::MAX),
+    ] {
+        let bytes = headers(file_size, 54, width, height, 24, 0, 0, 0);
+        match Image::from_reader(&mut &bytes[..]) {
+            Err(Error::InvalidImage(message)) => assert!(message.contains("file size")),
+            other => panic!("{}x{}: {:?}", width, height, other),
+        }
+    }
+}
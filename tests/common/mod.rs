@@ -0,0 +1,177 @@
+//! Synthetic objects, reference fields and error metrics shared by the
+//! integration tests.
+
+#![allow(dead_code)]
+
+use ndarray::{Array2, Zip};
+use num::Complex;
+use std::f64::consts::PI;
+
+#[allow(unused_imports)]
+pub use holography::bench::phase_rms;
+use holography::cgh::CghConfig;
+use holography::object::Object3d;
+use holography::point::Point;
+use holography::propagation::{Method, Propagator};
+
+pub const WIDTH: u32 = 128;
+pub const HEIGHT: u32 = 128;
+pub const PIXEL_PITCH: f64 = 8.0e-6;
+pub const WAVELENGTH: f64 = 520.0e-9;
+/// Object distance in pixels (50mm). Far enough for the paraxial
+/// approximation to hold over the small hologram, without aliasing.
+pub const DISTANCE: f64 = 6250.0;
+pub const SCALE: f64 = 4.0;
+
+/// A 128x128 hologram with the object centered `DISTANCE` pixels away. The
+/// offset is a whole number of pixels and `SCALE` an integer, so objects with
+/// integer coordinates land on pixel centers.
+pub fn config() -> CghConfig {
+    CghConfig {
+        image_width: WIDTH,
+        image_height: HEIGHT,
+        pixel_pitch: PIXEL_PITCH,
+        wavelength: WAVELENGTH,
+        offset: Point::new(WIDTH as f64 / 2.0, HEIGHT as f64 / 2.0, DISTANCE),
+        scalar: SCALE,
+        reference: None,
+    }
+}
+
+/// Depth of the object center in meters, for reconstruction.
+pub fn depth() -> f64 {
+    DISTANCE * PIXEL_PITCH
+}
+
+pub fn single_point() -> Object3d {
+    Object3d::new(vec![Point::new(1.0, -2.0, 0.0)])
+}
+
+/// Nine points along x, with decreasing amplitudes.
+pub fn line() -> Object3d {
+    let points = (-4..=4).map(|x| Point::new(x as f64, 0.0, 0.0)).collect();
+    let amplitudes = (0..9).map(|i| 1.0 - i as f64 / 18.0).collect();
+    Object3d::new(points).with_amplitudes(amplitudes)
+}
+
+/// The 27 points of a 3x3x3 lattice, a coarse subset of a cube, with
+/// reproducible random phases.
+pub fn cube_subset() -> Object3d {
+    let mut points = Vec::new();
+    for z in [-2.0, 0.0, 2.0] {
+        for y in [-2.0, 0.0, 2.0] {
+            for x in [-2.0, 0.0, 2.0] {
+                points.push(Point::new(x, y, z));
+            }
+        }
+    }
+    Object3d::new(points).with_random_phases(7)
+}
+
+/// The synthetic objects, by name.
+pub fn objects() -> Vec<(&'static str, Object3d)> {
+    vec![
+        ("single point", single_point()),
+        ("line", line()),
+        ("cube subset", cube_subset()),
+    ]
+}
+
+fn points(config: &CghConfig, object: &Object3d) -> Vec<(Point, f64, f64)> {
+    let points = object.points().iter().zip(object.amplitudes());
+    points
+        .zip(object.phases())
+        .map(|((&point, &amplitude), &phase)| {
+            ((point * config.scalar) + config.offset, amplitude, phase)
+        })
+        .collect()
+}
+
+/// `exp(i(kr + φ)) / r` summed over the points, where `r` is the exact
+/// distance to each pixel. The path length is split into the axial distance
+/// and the (small) excess `ρ² / (r + z)`, so the phase keeps full precision.
+pub fn spherical_reference(config: &CghConfig, object: &Object3d) -> Array2<Complex<f64>> {
+    let points = points(config, object);
+    let cycles_per_pixel = config.pixel_pitch / config.wavelength;
+
+    Array2::from_shape_fn(
+        (config.image_height as usize, config.image_width as usize),
+        |(y, x)| {
+            points
+                .iter()
+                .map(|&(point, amplitude, phase)| {
+                    let rho2 = (x as f64 - point.x).powi(2) + (y as f64 - point.y).powi(2);
+                    let r = (rho2 + point.z.powi(2)).sqrt();
+                    let excess = rho2 / (r + point.z);
+                    let cycles =
+                        (cycles_per_pixel * point.z).fract() + (cycles_per_pixel * excess).fract();
+                    Complex::from_polar(amplitude / r, 2.0 * PI * cycles + phase)
+                })
+                .sum()
+        },
+    )
+}
+
+/// The paraxial (Fresnel) field `exp(i(k(z + ρ² / 2z) + φ))`, the model of
+/// the Fresnel and recurrence processors.
+pub fn paraxial_reference(config: &CghConfig, object: &Object3d) -> Array2<Complex<f64>> {
+    let points = points(config, object);
+    let cycles_per_pixel = config.pixel_pitch / config.wavelength;
+
+    Array2::from_shape_fn(
+        (config.image_height as usize, config.image_width as usize),
+        |(y, x)| {
+            points
+                .iter()
+                .map(|&(point, amplitude, phase)| {
+                    let rho2 = (x as f64 - point.x).powi(2) + (y as f64 - point.y).powi(2);
+                    let cycles = (cycles_per_pixel * point.z).fract()
+                        + (cycles_per_pixel * rho2 / (2.0 * point.z)).fract();
+                    Complex::from_polar(amplitude, 2.0 * PI * cycles + phase)
+                })
+                .sum()
+        },
+    )
+}
+
+/// Intensity of `field` propagated back to `depth` meters.
+pub fn reconstruct(config: &CghConfig, field: &Array2<Complex<f64>>, depth: f64) -> Array2<f64> {
+    Propagator::new(config.pixel_pitch, config.wavelength)
+        .propagate(field, -depth, Method::AngularSpectrum)
+        .mapv(|value| value.norm_sqr())
+}
+
+/// PSNR in dB of the reconstruction of `field` at `depth` against that of
+/// `reference`, each normalized to unit total intensity, with the peak of the
+/// reference reconstruction as the signal.
+pub fn psnr(
+    config: &CghConfig,
+    field: &Array2<Complex<f64>>,
+    reference: &Array2<Complex<f64>>,
+    depth: f64,
+) -> f64 {
+    let normalize = |intensity: Array2<f64>| {
+        let total = intensity.sum();
+        intensity / total
+    };
+    let image = normalize(reconstruct(config, field, depth));
+    let expected = normalize(reconstruct(config, reference, depth));
+
+    let peak = expected.iter().copied().fold(0.0, f64::max);
+    let mse = Zip::from(&image)
+        .and(&expected)
+        .fold(0.0, |sum, &a, &b| sum + (a - b).powi(2))
+        / image.len() as f64;
+
+    10.0 * (peak.powi(2) / mse).log10()
+}
+
+/// Largest difference between two fields, relative to the largest magnitude
+/// of `reference`.
+pub fn max_difference(field: &Array2<Complex<f64>>, reference: &Array2<Complex<f64>>) -> f64 {
+    let scale = reference.iter().map(|r| r.norm()).fold(0.0, f64::max);
+    Zip::from(field)
+        .and(reference)
+        .fold(0.0, |max: f64, &f, &r| max.max((f - r).norm()))
+        / scale
+}
@@ -0,0 +1,60 @@
+//! Reconstruction quality of the IFTA algorithms.
+
+use ndarray::Array2;
+
+use holography::fft::{fft2, Direction};
+use holography::ifta::{Algorithm, Ifta};
+
+const WIDTH: usize = 128;
+const HEIGHT: usize = 128;
+const TARGET: usize = 32;
+
+/// Share of the reconstructed energy within the target, and the relative
+/// standard deviation of the intensity there.
+fn reconstruct(algorithm: Algorithm) -> (f64, f64) {
+    let target = Array2::from_elem((TARGET, TARGET), 1.0);
+    let retrieval = Ifta::new(algorithm).run(&target, WIDTH, HEIGHT);
+
+    let mut image = retrieval.field;
+    fft2(&mut image, Direction::Forward);
+
+    // The target is centered, so in FFT order it wraps around the corners.
+    let inside = |k: usize, n: usize| (k + n - n / 2) % n;
+    let top = (HEIGHT - TARGET) / 2;
+    let left = (WIDTH - TARGET) / 2;
+    let signal = (0..TARGET)
+        .flat_map(|y| (0..TARGET).map(move |x| (y, x)))
+        .map(|(y, x)| image[(inside(top + y, HEIGHT), inside(left + x, WIDTH))].norm_sqr())
+        .collect::<Vec<_>>();
+
+    let total = image.iter().map(|e| e.norm_sqr()).sum::<f64>();
+    let sum = signal.iter().sum::<f64>();
+    let mean = sum / signal.len() as f64;
+    let variance = signal.iter().map(|i| (i - mean).powi(2)).sum::<f64>() / signal.len() as f64;
+
+    (sum / total, variance.sqrt() / mean)
+}
+
+#[test]
+fn algorithms_concentrate_light_on_target() {
+    for (algorithm, min_efficiency, max_nonuniformity) in [
+        (Algorithm::GerchbergSaxton, 0.9, 0.3),
+        (Algorithm::WeightedGerchbergSaxton, 0.9, 0.05),
+        (Algorithm::Mraf { mixing: 0.5 }, 0.8, 0.05),
+        (Algorithm::Mraf { mixing: 0.9 }, 0.9, 0.3),
+    ] {
+        let (efficiency, nonuniformity) = reconstruct(algorithm);
+        assert!(
+            efficiency >= min_efficiency,
+            "{:?}: efficiency {}",
+            algorithm,
+            efficiency
+        );
+        assert!(
+            nonuniformity <= max_nonuniformity,
+            "{:?}: nonuniformity {}",
+            algorithm,
+            nonuniformity
+        );
+    }
+}
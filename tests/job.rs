@@ -0,0 +1,254 @@
+//! Job files and their validation.
+
+use std::fs;
+
+use holography::cgh::occlusion::Occlusion;
+use holography::cgh::SampleDepth;
+use holography::error::Error;
+use holography::job::{Job, ProcessorKind, Trig, TrigKind};
+use holography::lut::LutMode;
+use holography::object::ObjectFormat;
+use holography::point::Point;
+use holography::raster::ImageFormat;
+
+/// The examples in `jobs/` stand in for the former demo binaries, so they
+/// have to stay valid as the job format evolves.
+#[test]
+fn example_jobs_parse() {
+    let mut count = 0;
+    for entry in fs::read_dir("jobs").unwrap() {
+        let path = entry.unwrap().path();
+        let job = Job::open(&path).unwrap_or_else(|error| panic!("{}", error));
+        job.resolved()
+            .unwrap_or_else(|error| panic!("{}: {}", path.display(), error));
+        count += 1;
+    }
+
+    assert!(count > 0, "no example jobs found");
+}
+
+fn job() -> Job {
+    Job::from_toml(
+        r#"
+        [input]
+        path = "data/cube284.3d"
+
+        [output]
+        path = "out/job.bmp"
+        "#,
+    )
+    .unwrap()
+}
+
+/// A job with every optional part set.
+fn full_job() -> Job {
+    Job::from_toml(
+        r#"
+        processor = "fresnel-parallel"
+        encoder = "error-diffusion"
+
+        [trig]
+        kind = "lut"
+        lut-size = 4096
+        lut-mode = "linear"
+
+        [settings]
+        light = { x = -1.0, y = -1.0, z = -2.0 }
+        layers = 8
+        layer-method = "fresnel"
+        amplitude-bias = 2.0
+        diffusion-levels = 4
+        diffusion-kernel = "jarvis-judice-ninke"
+        layer-random-phase = 3
+
+        [settings.occlusion]
+        block-size = 32
+        point-radius = 0.5
+
+        [hologram]
+        width = 640
+        height = 480
+        pixel-pitch = 4e-6
+        wavelength = 532e-9
+        wavelengths = [638e-9, 520e-9, 450e-9]
+        scale = 10.0
+        offset = { x = 300.0, y = 200.0, z = 50000.0 }
+
+        [hologram.reference]
+        amplitude = 0.5
+        mode = "bipolar"
+        wave = { type = "plane", angle-x = 0.01, angle-y = -0.02 }
+
+        [input]
+        path = "data/cube.obj"
+        sample-spacing = 2.0
+        random-phase = 7
+        color-by-position = true
+        transform = { scale = 2.0, rotate = [0.0, 30.0, 0.0], translate = [1.0, 2.0, 3.0] }
+
+        [output]
+        path = "out/job.png"
+        sample-depth = "16"
+        sidecar = false
+        "#,
+    )
+    .unwrap()
+}
+
+/// Makes a valid job invalid.
+type Breakage = fn(&mut Job);
+
+fn assert_invalid(job: &Job, message: &str) {
+    match job.validate() {
+        Err(Error::InvalidJob(error)) => {
+            assert!(error.contains(message), "`{}` lacks `{}`", error, message)
+        }
+        other => panic!("expected an invalid job ({}), got {:?}", message, other),
+    }
+}
+
+#[test]
+fn validation_rejects_each_invalid_field() {
+    let cases: [(&str, Breakage); 22] = [
+        ("width and height", |job| job.hologram.width = 0),
+        ("width and height", |job| job.hologram.height = 0),
+        ("pixel-pitch", |job| job.hologram.pixel_pitch = 0.0),
+        ("pixel-pitch", |job| job.hologram.pixel_pitch = f64::NAN),
+        ("wavelength must", |job| job.hologram.wavelength = -520e-9),
+        ("wavelengths", |job| {
+            job.hologram.wavelengths = Some([638e-9, 0.0, 450e-9])
+        }),
+        ("scale", |job| job.hologram.scale = 0.0),
+        ("offset must be finite", |job| {
+            job.hologram.offset = Some(Point::new(0.0, f64::INFINITY, 1.0))
+        }),
+        ("offset.z", |job| {
+            job.hologram.offset = Some(Point::new(960.0, 540.0, 0.0))
+        }),
+        ("lut-size", |job| job.trig.lut_size = 300),
+        ("transform", |job| job.input.transform.scale = 0.0),
+        ("transform", |job| job.input.transform.rotate[1] = f64::NAN),
+        ("sample-spacing", |job| {
+            job.input.sample_spacing = Some(-1.0)
+        }),
+        ("layers", |job| job.settings.layers = 0),
+        ("ambient", |job| job.settings.ambient = 1.5),
+        ("amplitude-bias", |job| job.settings.amplitude_bias = 0.0),
+        ("diffusion-levels", |job| {
+            job.settings.diffusion_levels = Some(1)
+        }),
+        ("bits-per-pixel must", |job| {
+            job.output.bits_per_pixel = Some(2)
+        }),
+        ("grayscale BMP output", |job| {
+            job.output.path = "out/job.png".into();
+            job.output.bits_per_pixel = Some(1);
+        }),
+        ("does not support occlusion", |job| {
+            job.processor = ProcessorKind::Recurrence;
+            job.settings.occlusion = Some(Occlusion::default());
+        }),
+        ("8- or 16-bit samples", |job| {
+            job.hologram.wavelengths = Some([638e-9, 520e-9, 450e-9]);
+            job.output.sample_depth = SampleDepth::Float;
+        }),
+        ("8- or 16-bit samples", |job| {
+            job.hologram.wavelengths = Some([638e-9, 520e-9, 450e-9]);
+            job.output.sample_depth = SampleDepth::FloatLevel;
+        }),
+    ];
+
+    job().validate().unwrap();
+    for (message, break_job) in cases {
+        let mut job = job();
+        break_job(&mut job);
+        assert_invalid(&job, message);
+    }
+}
+
+#[test]
+fn parsing_rejects_unknown_fields_and_values() {
+    for text in [
+        "[input]\npath = \"a.3d\"\n[output]\npath = \"a.bmp\"\n[hologram]\npitch = 1.0",
+        "processor = \"fastest\"\n[input]\npath = \"a.3d\"\n[output]\npath = \"a.bmp\"",
+        "[input]\npath = \"a.3d\"",
+        "[input]\npath = \"a.3d\"\n[output]\npath = \"a.bmp\"\n[trig]\nlut-size = 100",
+    ] {
+        assert!(
+            matches!(Job::from_toml(text), Err(Error::InvalidJob(_))),
+            "accepted:\n{}",
+            text
+        );
+    }
+}
+
+#[test]
+fn resolved_fills_in_defaults() {
+    let job = job();
+    assert!(job.hologram.offset.is_none());
+    assert!(job.input.format.is_none() && job.output.format.is_none());
+
+    let resolved = job.resolved().unwrap();
+    let offset = resolved.hologram.offset.unwrap();
+    assert_eq!((offset.x, offset.y), (960.0, 540.0));
+    assert!((offset.z - 1.0 / 8.0e-6).abs() < 1e-6);
+    assert_eq!(resolved.input.format, Some(ObjectFormat::Points));
+    assert_eq!(resolved.output.format, Some(ImageFormat::Bmp));
+
+    let mut unknown = job;
+    unknown.output.path = "out/job.unknown".into();
+    assert!(matches!(
+        unknown.resolved(),
+        Err(Error::UnsupportedFormat(_))
+    ));
+}
+
+/// The sidecar written next to a hologram is the resolved job as TOML, and
+/// must read back as the same job.
+#[test]
+fn resolved_jobs_round_trip() {
+    for job in [job(), full_job()] {
+        let resolved = job.resolved().unwrap();
+
+        let toml = resolved.to_toml();
+        assert_eq!(Job::from_toml(&toml).unwrap().to_toml(), toml);
+        let json = resolved.to_json();
+        assert_eq!(Job::from_json(&json).unwrap().to_json(), json);
+        assert_eq!(Job::from_json(&json).unwrap().to_toml(), toml);
+    }
+
+    let full = full_job().resolved().unwrap();
+    assert_eq!(full.trig.lut_size, 4096);
+    assert_eq!(full.settings.layers, 8);
+    assert_eq!(full.settings.diffusion_levels, Some(4));
+    assert_eq!(full.settings.occlusion.unwrap().block_size, 32);
+    assert_eq!(full.hologram.wavelengths, Some([638e-9, 520e-9, 450e-9]));
+    assert_eq!(full.input.transform.rotate, [0.0, 30.0, 0.0]);
+    assert!(full.hologram.reference.is_some());
+}
+
+#[test]
+fn trig_build_rejects_unsupported_sizes() {
+    for trig in Trig::luts() {
+        assert!(trig.build().is_ok(), "{}", trig);
+    }
+
+    for kind in [TrigKind::Naive, TrigKind::Lut] {
+        let trig = Trig {
+            kind,
+            lut_size: 300,
+            lut_mode: LutMode::Linear,
+        };
+        assert!(matches!(trig.build(), Err(Error::InvalidJob(_))));
+        assert!(trig.error(10).is_err());
+    }
+}
+
+#[test]
+fn polygon_jobs_need_faces() {
+    let mut job = job();
+    job.processor = ProcessorKind::Polygon;
+    job.input.path = "data/point.obj".into();
+
+    assert!(matches!(job.raster(), Err(Error::InvalidObject(_))));
+}
@@ -0,0 +1,141 @@
+//! OBJ and STL mesh loading and face sampling.
+
+use std::io::Cursor;
+
+use holography::error::Error;
+use holography::object::Object3d;
+
+fn coordinates(object: &Object3d) -> Vec<[f64; 3]> {
+    object.points().iter().map(|p| [p.x, p.y, p.z]).collect()
+}
+
+fn obj(text: &str) -> Result<Object3d, Error> {
+    Object3d::from_obj_reader(&mut Cursor::new(text))
+}
+
+fn stl(bytes: &[u8]) -> Result<Object3d, Error> {
+    Object3d::from_stl_reader(&mut &bytes[..])
+}
+
+/// A binary STL of `facets` with the 80-byte `header`.
+fn binary_stl(header: &[u8], facets: &[[[f32; 3]; 3]]) -> Vec<u8> {
+    let mut bytes = header.to_vec();
+    bytes.resize(80, 0);
+    bytes.extend((facets.len() as u32).to_le_bytes());
+    for facet in facets {
+        bytes.extend([0; 12]); // normal
+        for value in facet.iter().flatten() {
+            bytes.extend(value.to_le_bytes());
+        }
+        bytes.extend([0; 2]); // attribute
+    }
+    bytes
+}
+
+/// An ASCII STL of `facets`.
+fn ascii_stl(facets: &[[[f32; 3]; 3]]) -> String {
+    let mut text = String::from("solid mesh\n");
+    for facet in facets {
+        text += "facet normal 0 0 1\n  outer loop\n";
+        for [x, y, z] in facet {
+            text += &format!("    vertex {} {} {}\n", x, y, z);
+        }
+        text += "  endloop\nendfacet\n";
+    }
+    text + "endsolid mesh\n"
+}
+
+const SQUARE: [[[f32; 3]; 3]; 2] = [
+    [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0]],
+    [[0.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0]],
+];
+
+#[test]
+fn obj_polygons_become_triangle_fans() {
+    let object = obj("# a square and a triangle\n\
+         v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nvn 0 0 1\n\
+         f 1/1/1 2/2/1 3/3/1 4/4/1\n\
+         v 0 0 1\n\
+         f -1 1//1 2\n")
+    .unwrap();
+
+    assert_eq!(object.size(), 5);
+    assert_eq!(coordinates(&object)[4], [0.0, 0.0, 1.0]);
+    assert_eq!(object.faces(), &[[0, 1, 2], [0, 2, 3], [4, 0, 1]]);
+}
+
+#[test]
+fn obj_errors_name_the_line() {
+    for (text, line) in [
+        ("v 0 0 0\nv 1 0 0\nf 1 2\n", "line 3"),
+        ("v 0 0 0\nf 1 1 4\n", "line 2"),
+        ("v 0 0 0\nf 0 1 1\n", "line 2"),
+        ("v 0 zero 0\n", "line 1"),
+    ] {
+        match obj(text) {
+            Err(Error::InvalidObject(message)) => {
+                assert!(message.contains(line), "{}: {}", line, message)
+            }
+            other => panic!("{:?} for\n{}", other, text),
+        }
+    }
+}
+
+#[test]
+fn stl_shared_vertices_are_merged() {
+    let ascii = ascii_stl(&SQUARE);
+    // Some exporters start binary headers with `solid` as well.
+    let binary = binary_stl(b"solid exported as binary", &SQUARE);
+
+    for object in [stl(ascii.as_bytes()), stl(&binary)] {
+        let object = object.unwrap();
+        assert_eq!(
+            coordinates(&object),
+            [
+                [0.0, 0.0, 0.0],
+                [1.0, 0.0, 0.0],
+                [1.0, 1.0, 0.0],
+                [0.0, 1.0, 0.0]
+            ]
+        );
+        assert_eq!(object.faces(), &[[0, 1, 2], [0, 2, 3]]);
+    }
+}
+
+#[test]
+fn malformed_stl_is_rejected() {
+    let mut truncated = binary_stl(b"mesh", &SQUARE);
+    truncated.truncate(truncated.len() - 10);
+    let missing_vertex = "solid s\nfacet\nouter loop\nvertex 0 0 0\nvertex 1 0 0\nendloop\n\
+                          endfacet\nendsolid s\n";
+
+    for bytes in [
+        &truncated[..],
+        &truncated[..40],
+        missing_vertex.as_bytes(),
+        b"solid empty\nendsolid empty\n",
+    ] {
+        assert!(matches!(stl(bytes), Err(Error::InvalidObject(_))));
+    }
+}
+
+/// Sampled points cover the faces at about the spacing, after the vertices
+/// and without duplicates along the shared edge.
+#[test]
+fn sampled_faces_keep_vertices_and_faces() {
+    let square = stl(&binary_stl(b"mesh", &SQUARE)).unwrap();
+    let sampled = square.sample_faces(0.25);
+
+    assert_eq!(coordinates(&sampled)[..4], coordinates(&square)[..]);
+    assert_eq!(sampled.faces(), square.faces());
+    // The diagonal of √2 sets 6 steps per face: a 7x7 grid over the square.
+    assert_eq!(sampled.size(), 49);
+    for [x, y, z] in coordinates(&sampled) {
+        assert!((0.0..=1.0).contains(&x) && (0.0..=1.0).contains(&y) && z == 0.0);
+        assert!(((x * 6.0).round() - x * 6.0).abs() < 1e-9);
+    }
+    assert!(sampled
+        .amplitudes()
+        .iter()
+        .all(|&a| (a - 1.0).abs() < 1e-12));
+}
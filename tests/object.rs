@@ -0,0 +1,95 @@
+//! Reading `.3d` point lists, and the errors of object and image I/O.
+
+use std::error::Error as _;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use holography::bmp::Image;
+use holography::error::Error;
+use holography::object::Object3d;
+
+fn points_file(points: &[[i32; 3]], count: u32) -> Vec<u8> {
+    let mut bytes = count.to_le_bytes().to_vec();
+    for point in points {
+        for coordinate in point {
+            bytes.extend(coordinate.to_le_bytes());
+        }
+    }
+    bytes
+}
+
+/// A file in the temporary directory, removed when dropped.
+struct TempFile(PathBuf);
+
+impl TempFile {
+    fn new(name: &str, bytes: &[u8]) -> Self {
+        let path = std::env::temp_dir().join(format!("holography-{}-{}", std::process::id(), name));
+        fs::write(&path, bytes).unwrap();
+        TempFile(path)
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+#[test]
+fn points_read_back() {
+    let bytes = points_file(&[[1, -2, 3], [-4, 5, 600]], 2);
+    let object = Object3d::from_reader(&mut &bytes[..]).unwrap();
+
+    let points: Vec<_> = object.points().iter().map(|p| [p.x, p.y, p.z]).collect();
+    assert_eq!(points, [[1.0, -2.0, 3.0], [-4.0, 5.0, 600.0]]);
+    assert_eq!(object.amplitudes(), &[1.0, 1.0]);
+}
+
+#[test]
+fn truncated_points_are_reported() {
+    let mut bytes = points_file(&[[1, 2, 3], [4, 5, 6]], 2);
+    bytes.truncate(bytes.len() - 1);
+
+    let error = Object3d::from_reader(&mut &bytes[..]).unwrap_err();
+    assert!(matches!(
+        error,
+        Error::TruncatedPoint { index: 1, count: 2 }
+    ));
+    assert_eq!(
+        error.to_string(),
+        "truncated point record at index 1 (expected 2 points)"
+    );
+}
+
+/// `open` rejects counts the file cannot hold before reading, and names the
+/// file in its errors.
+#[test]
+fn file_errors_carry_the_path() {
+    let file = TempFile::new("implausible.3d", &points_file(&[[1, 2, 3]], 1000));
+    let error = Object3d::open(&file.0).unwrap_err();
+    let Error::File { path, source } = &error else {
+        panic!("expected a file error, got {:?}", error);
+    };
+    assert_eq!(path, &file.0);
+    assert!(matches!(
+        **source,
+        Error::ImplausiblePointCount {
+            count: 1000,
+            stream_len: 16
+        }
+    ));
+    assert!(error.to_string().starts_with(&file.0.display().to_string()));
+    assert!(error.source().is_some());
+
+    let missing = file.0.with_extension("missing.bmp");
+    match Image::open(&missing) {
+        Err(Error::File { path, source }) => {
+            assert_eq!(path, missing);
+            assert!(
+                matches!(*source, Error::Io(ref error) if error.kind() == io::ErrorKind::NotFound)
+            );
+        }
+        other => panic!("expected a file error, got {:?}", other),
+    }
+}
@@ -0,0 +1,110 @@
+//! PLY point-cloud import.
+
+use std::io::Cursor;
+
+use holography::error::Error;
+use holography::object::Object3d;
+
+fn read(bytes: &[u8]) -> Result<Object3d, Error> {
+    Object3d::from_ply_reader(&mut Cursor::new(bytes))
+}
+
+fn coordinates(object: &Object3d) -> Vec<[f64; 3]> {
+    object.points().iter().map(|p| [p.x, p.y, p.z]).collect()
+}
+
+/// A binary PLY of two vertices with float coordinates and an 8-bit
+/// intensity, followed by one triangle.
+fn binary(format: &str, encode: fn(f32) -> [u8; 4], encode_u32: fn(u32) -> [u8; 4]) -> Vec<u8> {
+    let mut bytes = format!(
+        "ply\nformat {} 1.0\nelement vertex 2\nproperty float x\nproperty float y\n\
+         property float z\nproperty uchar intensity\nelement face 1\n\
+         property list uchar uint vertex_indices\nend_header\n",
+        format
+    )
+    .into_bytes();
+    for (point, intensity) in [([1.0, -2.0, 0.5], 255), ([0.0, 3.0, -1.5], 64)] {
+        for value in point {
+            bytes.extend(encode(value));
+        }
+        bytes.push(intensity);
+    }
+    bytes.push(3);
+    for index in [0, 1, 0] {
+        bytes.extend(encode_u32(index));
+    }
+
+    bytes
+}
+
+#[test]
+fn ascii_colors_weight_amplitudes() {
+    let object = read(
+        b"ply\nformat ascii 1.0\ncomment two points\nelement vertex 2\n\
+          property float x\nproperty float y\nproperty float z\n\
+          property uchar red\nproperty uchar green\nproperty uchar blue\n\
+          element face 1\nproperty list uchar int vertex_indices\nend_header\n\
+          1 2 3 255 255 255\n-1 0 0.5 255 0 0\n3 0 1 0\n",
+    )
+    .unwrap();
+
+    assert_eq!(coordinates(&object), [[1.0, 2.0, 3.0], [-1.0, 0.0, 0.5]]);
+    let amplitudes = object.amplitudes();
+    assert!((amplitudes[0] - 1.0).abs() < 1e-12);
+    assert!((amplitudes[1] - 0.299f64.sqrt()).abs() < 1e-12);
+    assert_eq!(object.colors().unwrap()[1], [1.0, 0.0, 0.0]);
+}
+
+#[test]
+fn binary_in_either_endianness() {
+    for bytes in [
+        binary("binary_little_endian", f32::to_le_bytes, u32::to_le_bytes),
+        binary("binary_big_endian", f32::to_be_bytes, u32::to_be_bytes),
+    ] {
+        let object = read(&bytes).unwrap();
+
+        assert_eq!(coordinates(&object), [[1.0, -2.0, 0.5], [0.0, 3.0, -1.5]]);
+        let amplitudes = object.amplitudes();
+        assert_eq!(amplitudes[0], 1.0);
+        assert!((amplitudes[1] - (64.0f64 / 255.0).sqrt()).abs() < 1e-12);
+        assert!(object.colors().is_none());
+    }
+}
+
+#[test]
+fn truncated_vertices_are_reported() {
+    let mut bytes = binary("binary_little_endian", f32::to_le_bytes, u32::to_le_bytes);
+    // Cut the second vertex short, dropping the face.
+    let header = bytes.len() - 2 * 13 - 13;
+    bytes.truncate(header + 13 + 6);
+
+    assert!(matches!(
+        read(&bytes),
+        Err(Error::TruncatedPoint { index: 1, count: 2 })
+    ));
+
+    let ascii = b"ply\nformat ascii 1.0\nelement vertex 3\nproperty float x\n\
+                  property float y\nproperty float z\nend_header\n0 0 0\n1 1 1\n";
+    assert!(matches!(
+        read(ascii),
+        Err(Error::TruncatedPoint { index: 2, count: 3 })
+    ));
+}
+
+/// A list count far beyond the end of the file fails at the end of the
+/// file instead of reading on.
+#[test]
+fn oversized_list_counts_are_truncated_elements() {
+    let mut bytes = b"ply\nformat binary_little_endian 1.0\nelement vertex 1\n\
+                      property float x\nproperty float y\nproperty float z\n\
+                      element face 1\nproperty list uint int vertex_indices\nend_header\n"
+        .to_vec();
+    bytes.extend([0; 12]);
+    bytes.extend(u32::MAX.to_le_bytes());
+    bytes.extend([0; 8]);
+
+    match read(&bytes) {
+        Err(Error::InvalidObject(message)) => assert!(message.contains("face"), "{}", message),
+        other => panic!("expected a truncated face, got {:?}", other),
+    }
+}
@@ -0,0 +1,299 @@
+//! PNG output and its zlib encoder, checked with a small inflater for the
+//! stored and fixed-code blocks the encoder writes.
+
+use holography::error::Error;
+use holography::raster::zlib::{adler32, compress, Compression};
+use holography::raster::{ImageFormat, Pixels, Raster};
+
+const LENGTH_BASE: [usize; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u32; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [usize; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u32; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+struct Bits<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl Bits<'_> {
+    fn bit(&mut self) -> u32 {
+        let bit = (self.bytes[self.position / 8] >> (self.position % 8)) & 1;
+        self.position += 1;
+        bit as u32
+    }
+
+    /// `count` bits, least significant first.
+    fn bits(&mut self, count: u32) -> u32 {
+        (0..count).fold(0, |value, i| value | self.bit() << i)
+    }
+
+    /// A Huffman code of `count` bits, most significant first.
+    fn code(&mut self, count: u32) -> u32 {
+        (0..count).fold(0, |code, _| code << 1 | self.bit())
+    }
+
+    fn byte_aligned(&mut self) -> &[u8] {
+        self.position = self.position.div_ceil(8) * 8;
+        &self.bytes[self.position / 8..]
+    }
+}
+
+/// Literal/length symbol of the fixed Huffman codes.
+fn fixed_symbol(bits: &mut Bits) -> usize {
+    let code = bits.code(7);
+    if code <= 23 {
+        return 256 + code as usize;
+    }
+    let code = code << 1 | bits.bit();
+    match code {
+        0x30..=0xbf => (code - 0x30) as usize,
+        0xc0..=0xc7 => (280 + code - 0xc0) as usize,
+        _ => (144 + (code << 1 | bits.bit()) - 0x190) as usize,
+    }
+}
+
+/// Decodes a zlib stream of stored and fixed-code blocks, checking the
+/// header and the Adler-32 trailer.
+fn inflate(stream: &[u8]) -> Vec<u8> {
+    assert_eq!(stream[0], 0x78, "deflate with a 32K window");
+    assert_eq!(u16::from_be_bytes([stream[0], stream[1]]) % 31, 0);
+
+    let mut bits = Bits {
+        bytes: &stream[2..],
+        position: 0,
+    };
+    let mut output = Vec::new();
+    loop {
+        let last = bits.bit() == 1;
+        match bits.bits(2) {
+            0 => {
+                let block = bits.byte_aligned();
+                let length = u16::from_le_bytes([block[0], block[1]]);
+                assert_eq!(!length, u16::from_le_bytes([block[2], block[3]]));
+                output.extend_from_slice(&block[4..4 + length as usize]);
+                bits.position += (4 + length as usize) * 8;
+            }
+            1 => loop {
+                let symbol = fixed_symbol(&mut bits);
+                match symbol {
+                    0..=255 => output.push(symbol as u8),
+                    256 => break,
+                    _ => {
+                        let index = symbol - 257;
+                        let length = LENGTH_BASE[index] + bits.bits(LENGTH_EXTRA[index]) as usize;
+                        let index = bits.code(5) as usize;
+                        let distance =
+                            DISTANCE_BASE[index] + bits.bits(DISTANCE_EXTRA[index]) as usize;
+                        for _ in 0..length {
+                            output.push(output[output.len() - distance]);
+                        }
+                    }
+                }
+            },
+            kind => panic!("unexpected block type {}", kind),
+        }
+        if last {
+            break;
+        }
+    }
+
+    let trailer = &bits.byte_aligned()[..4];
+    assert_eq!(
+        u32::from_be_bytes(trailer.try_into().unwrap()),
+        adler32(&output)
+    );
+    output
+}
+
+/// Bytes from a linear congruential generator, which do not compress.
+fn noise(length: usize) -> Vec<u8> {
+    let mut state = 1u32;
+    (0..length)
+        .map(|_| {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            (state >> 24) as u8
+        })
+        .collect()
+}
+
+#[test]
+fn adler32_known_value() {
+    assert_eq!(adler32(b""), 1);
+    assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+}
+
+#[test]
+fn zlib_streams_inflate_to_their_input() {
+    let repeated = b"hologram ".repeat(1000);
+    let runs: Vec<u8> = (0..70000).map(|i| (i / 300) as u8).collect();
+    for data in [Vec::new(), b"a".to_vec(), repeated, runs, noise(70000)] {
+        for compression in [Compression::Stored, Compression::Fixed] {
+            let stream = compress(&data, compression);
+            assert_eq!(inflate(&stream), data, "{:?}", compression);
+        }
+    }
+
+    // Repetitive data is actually compressed.
+    let stream = compress(&b"hologram ".repeat(1000), Compression::Fixed);
+    assert!(stream.len() < 200, "{} bytes", stream.len());
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                crc >> 1 ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// The chunks of a PNG file as `(kind, data)`, checking their CRCs.
+fn chunks(bytes: &[u8]) -> Vec<(String, Vec<u8>)> {
+    assert_eq!(
+        bytes[..8],
+        [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n']
+    );
+
+    let mut chunks = Vec::new();
+    let mut rest = &bytes[8..];
+    while !rest.is_empty() {
+        let length = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+        let (kind, data) = (&rest[4..8], &rest[8..8 + length]);
+        let crc = u32::from_be_bytes(rest[8 + length..12 + length].try_into().unwrap());
+        assert_eq!(crc, crc32(&rest[4..8 + length]), "CRC of {:?}", kind);
+
+        chunks.push((String::from_utf8(kind.to_vec()).unwrap(), data.to_vec()));
+        rest = &rest[12 + length..];
+    }
+
+    chunks
+}
+
+/// Reverses the PNG row filters.
+fn unfilter(filtered: &[u8], stride: usize, pixel_size: usize) -> Vec<u8> {
+    let mut rows: Vec<u8> = Vec::new();
+    for (y, row) in filtered.chunks_exact(stride + 1).enumerate() {
+        let start = y * stride;
+        for (i, &x) in row[1..].iter().enumerate() {
+            let a = if i >= pixel_size {
+                rows[start + i - pixel_size]
+            } else {
+                0
+            };
+            let b = if y > 0 { rows[start + i - stride] } else { 0 };
+            let c = if y > 0 && i >= pixel_size {
+                rows[start + i - stride - pixel_size]
+            } else {
+                0
+            };
+            let predictor = match row[0] {
+                0 => 0,
+                1 => a,
+                2 => b,
+                3 => ((a as u16 + b as u16) / 2) as u8,
+                4 => {
+                    let p = a as i16 + b as i16 - c as i16;
+                    let (pa, pb, pc) = (
+                        (p - a as i16).abs(),
+                        (p - b as i16).abs(),
+                        (p - c as i16).abs(),
+                    );
+                    if pa <= pb && pa <= pc {
+                        a
+                    } else if pb <= pc {
+                        b
+                    } else {
+                        c
+                    }
+                }
+                filter => panic!("unknown filter {}", filter),
+            };
+            rows.push(x.wrapping_add(predictor));
+        }
+    }
+
+    rows
+}
+
+#[test]
+fn png_chunks_and_pixels() {
+    let (width, height) = (7, 5);
+    let gradient = |x: u32, y: u32| (x * 30 + y * 11) as u16;
+    let cases = [
+        (
+            Pixels::Gray8(
+                (0..height)
+                    .flat_map(|y| (0..width).map(move |x| gradient(x, y) as u8))
+                    .collect(),
+            ),
+            8,
+            0,
+        ),
+        (
+            Pixels::Gray16(
+                (0..height)
+                    .flat_map(|y| (0..width).map(move |x| gradient(x, y) * 257))
+                    .collect(),
+            ),
+            16,
+            0,
+        ),
+        (Pixels::Rgb8(noise((width * height * 3) as usize)), 8, 2),
+    ];
+
+    for (pixels, bit_depth, color_type) in cases {
+        let mut raster = Raster::new(width, height, pixels);
+        raster.set_pixel_pitch(8.0e-6);
+        let mut bytes = Vec::new();
+        raster.to_writer(&mut bytes, ImageFormat::Png).unwrap();
+
+        let chunks = chunks(&bytes);
+        let kinds: Vec<_> = chunks.iter().map(|(kind, _)| kind.as_str()).collect();
+        assert_eq!(kinds, ["IHDR", "pHYs", "IDAT", "IEND"]);
+
+        let mut header = Vec::new();
+        header.extend(width.to_be_bytes());
+        header.extend(height.to_be_bytes());
+        header.extend([bit_depth, color_type, 0, 0, 0]);
+        assert_eq!(chunks[0].1, header);
+        // 125000 pixels per meter in both directions.
+        assert_eq!(chunks[1].1, [0, 1, 0xe8, 0x48, 0, 1, 0xe8, 0x48, 1]);
+
+        let channels = raster.pixels().channels();
+        let pixel_size = channels * bit_depth as usize / 8;
+        let stride = width as usize * pixel_size;
+        let samples = unfilter(&inflate(&chunks[2].1), stride, pixel_size);
+        let expected = match raster.pixels() {
+            Pixels::Gray8(samples) | Pixels::Rgb8(samples) => samples.clone(),
+            Pixels::Gray16(samples) => samples.iter().flat_map(|v| v.to_be_bytes()).collect(),
+            _ => unreachable!(),
+        };
+        assert_eq!(samples, expected);
+    }
+}
+
+#[test]
+fn png_rejects_float_samples() {
+    let raster = Raster::new(2, 1, Pixels::GrayF32(vec![0.0, 1.0]));
+    assert!(matches!(
+        raster.to_writer(&mut Vec::new(), ImageFormat::Png),
+        Err(Error::UnsupportedFormat(_))
+    ));
+}
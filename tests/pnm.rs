@@ -0,0 +1,89 @@
+//! PGM/PPM output and input.
+
+use holography::error::Error;
+use holography::raster::pnm::from_reader;
+use holography::raster::{ImageFormat, Pixels, Raster};
+
+fn read(bytes: &[u8]) -> Result<Raster, Error> {
+    from_reader(&mut &bytes[..])
+}
+
+fn written(raster: &Raster) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    raster.to_writer(&mut bytes, ImageFormat::Pnm).unwrap();
+    bytes
+}
+
+#[test]
+fn written_rasters_read_back() {
+    let cases = [
+        Pixels::Gray8(vec![0, 1, 128, 255, 7, 9]),
+        Pixels::Gray16(vec![0, 1, 256, 65535, 4097, 9]),
+        Pixels::Rgb8((0..18).map(|i| i * 14).collect()),
+        Pixels::Rgb16((0..18).map(|i| i * 3640).collect()),
+    ];
+
+    for pixels in cases {
+        let bytes = written(&Raster::new(3, 2, pixels.clone()));
+        let read = read(&bytes).unwrap();
+
+        assert_eq!((read.width(), read.height()), (3, 2));
+        assert_eq!(format!("{:?}", read.pixels()), format!("{:?}", pixels));
+    }
+}
+
+#[test]
+fn header_and_big_endian_samples() {
+    let raster = Raster::new(2, 1, Pixels::Gray16(vec![0x0102, 0xfffe]));
+    assert_eq!(written(&raster), b"P5\n2 1\n65535\n\x01\x02\xff\xfe");
+
+    let raster = Raster::new(1, 1, Pixels::Rgb8(vec![1, 2, 3]));
+    assert_eq!(written(&raster), b"P6\n1 1\n255\n\x01\x02\x03");
+}
+
+/// ASCII files with comments and a small maximum value are rescaled to the
+/// full 8-bit range.
+#[test]
+fn ascii_files_are_rescaled() {
+    let raster = read(b"P2\n# a comment\n3 1 # trailing\n15\n0 15 5\n").unwrap();
+    let Pixels::Gray8(samples) = raster.pixels() else {
+        panic!("8-bit samples expected");
+    };
+    assert_eq!(samples, &[0, 255, 85]);
+
+    let raster = read(b"P3 1 1 1023 1023 0 512").unwrap();
+    let Pixels::Rgb16(samples) = raster.pixels() else {
+        panic!("16-bit samples expected");
+    };
+    assert_eq!(samples, &[65535, 0, 32800]);
+}
+
+#[test]
+fn malformed_files_are_rejected() {
+    for bytes in [
+        &b"P5\n2 2\n255\n\x01\x02\x03"[..],
+        b"P5\n0 2\n255\n",
+        b"P5\n2 1\n0\n\x00\x00",
+        b"P2\n2 1\n10\n3 11\n",
+        b"P2\n2 1\n",
+        // Far more pixels than the file holds, rejected without allocating
+        // for all of them.
+        b"P5\n4000000000 4000000000\n255\n\x00",
+    ] {
+        assert!(
+            matches!(read(bytes), Err(Error::InvalidImage(_))),
+            "{:?}",
+            String::from_utf8_lossy(bytes)
+        );
+    }
+
+    assert!(matches!(
+        read(b"P7\n2 2\n255\n"),
+        Err(Error::UnsupportedFormat(_))
+    ));
+    let float = Raster::new(1, 1, Pixels::GrayF32(vec![0.5]));
+    assert!(matches!(
+        float.to_writer(&mut Vec::new(), ImageFormat::Pnm),
+        Err(Error::UnsupportedFormat(_))
+    ));
+}
@@ -0,0 +1,60 @@
+//! Edge cases of `CghProcessorPolygon`.
+
+mod common;
+
+use ndarray::Array2;
+use num::Complex;
+
+use holography::cgh::polygon::CghProcessorPolygon;
+use holography::cgh::CghProcessor;
+use holography::lut::TriFuncNaive;
+use holography::object::Object3d;
+use holography::point::Point;
+
+use common::*;
+
+fn triangle() -> Object3d {
+    Object3d::new(vec![
+        Point::new(-4.0, -4.0, 0.0),
+        Point::new(4.0, -4.0, 0.0),
+        Point::new(0.0, 4.0, 0.0),
+    ])
+    .with_faces(vec![[0, 2, 1]])
+}
+
+fn max_norm(field: &Array2<Complex<f64>>) -> f64 {
+    field.iter().map(|value| value.norm()).fold(0.0, f64::max)
+}
+
+/// Without a light direction only the ambient share of the intensity is
+/// left, with the same amplitude on every face.
+#[test]
+fn zero_light_is_ambient_only() {
+    let config = config();
+    let lit = CghProcessorPolygon {
+        light: Point::new(0.0, 0.0, 0.0),
+        ambient: 0.25,
+        ..Default::default()
+    };
+    let full = CghProcessorPolygon {
+        ambient: 1.0,
+        ..Default::default()
+    };
+
+    let field = lit.field(&TriFuncNaive, &config, &triangle());
+    let expected = full.field(&TriFuncNaive, &config, &triangle()) * 0.5;
+
+    assert!(field.iter().all(|value| value.is_finite()));
+    let difference = max_norm(&(&field - &expected)) / max_norm(&expected);
+    assert!(difference < 1e-12, "relative difference {}", difference);
+}
+
+#[test]
+fn points_without_faces_give_zero_field() {
+    let config = config();
+    let points = Object3d::new(triangle().points().to_vec());
+
+    let field = CghProcessorPolygon::default().field(&TriFuncNaive, &config, &points);
+
+    assert!(field.iter().all(|value| *value == 0.0.into()));
+}
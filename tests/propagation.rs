@@ -0,0 +1,31 @@
+//! Edge cases of `Propagator`.
+
+use ndarray::Array2;
+use num::Complex;
+
+use holography::propagation::{Method, Propagator};
+
+#[test]
+fn zero_distance_keeps_field() {
+    let field = Array2::from_shape_fn((8, 12), |(y, x)| Complex::new(x as f64, y as f64 - 3.0));
+    let propagator = Propagator::new(8.0e-6, 520.0e-9);
+
+    for method in [
+        Method::AngularSpectrum,
+        Method::FresnelConvolution,
+        Method::FresnelSingleFft,
+    ] {
+        let propagated = propagator.propagate(&field, 0.0, method);
+        let difference = (&propagated - &field)
+            .iter()
+            .map(|d| d.norm())
+            .fold(0.0, f64::max);
+        assert!(
+            difference < 1e-9,
+            "{:?}: differs by {:e}",
+            method,
+            difference
+        );
+    }
+    assert_eq!(propagator.single_fft_pitch(12, 0.0), 8.0e-6);
+}
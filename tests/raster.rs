@@ -0,0 +1,83 @@
+//! Sample formats of `generate_cgh_raster`.
+
+mod common;
+
+use holography::bmp::Image;
+use holography::cgh::encoding::{Amplitude, Kinoform};
+use holography::cgh::naive::CghProcessorNaive;
+use holography::cgh::{generate_cgh_raster, CghProcessor, SampleDepth};
+use holography::error::Error;
+use holography::lut::TriFuncNaive;
+use holography::raster::{ImageFormat, Pixels, Raster};
+
+use common::*;
+
+/// `Float` holds the phase of `CghProcessor::process` in radians, whatever
+/// the encoder.
+#[test]
+fn float_samples_are_raw_phase() {
+    let config = config();
+    let object = cube_subset();
+    let processor = CghProcessorNaive::default();
+    let phase = processor.process(&TriFuncNaive, &config, &object);
+
+    let raster = generate_cgh_raster(
+        &processor,
+        &Amplitude::default(),
+        &TriFuncNaive,
+        &config,
+        &object,
+        SampleDepth::Float,
+    );
+    let Pixels::GrayF32(samples) = raster.pixels() else {
+        panic!("float samples expected");
+    };
+
+    assert_eq!(samples.len(), phase.len());
+    for (&sample, &phase) in samples.iter().zip(phase.iter()) {
+        assert_eq!(sample, phase as f32);
+    }
+}
+
+#[test]
+fn float_level_samples_are_encoded() {
+    let config = config();
+    let object = cube_subset();
+    let raster = generate_cgh_raster(
+        &CghProcessorNaive::default(),
+        &Kinoform,
+        &TriFuncNaive,
+        &config,
+        &object,
+        SampleDepth::FloatLevel,
+    );
+    let Pixels::GrayF32(samples) = raster.pixels() else {
+        panic!("float samples expected");
+    };
+
+    assert!(samples.iter().all(|&level| (0.0..=1.0).contains(&level)));
+    assert!(samples.iter().any(|&level| level > 0.5));
+}
+
+/// Rasters with fewer bits per pixel keep the top bits of each sample in
+/// BMP output.
+#[test]
+fn bmp_output_with_fewer_bits() {
+    let mut raster = Raster::new(4, 1, Pixels::Gray8(vec![0, 255, 128, 127]));
+    raster.set_bits_per_pixel(1);
+    let mut bytes = Vec::new();
+    raster.to_writer(&mut bytes, ImageFormat::Bmp).unwrap();
+
+    let image = Image::from_reader(&mut &bytes[..]).unwrap();
+    let levels: Vec<_> = image
+        .coordinates()
+        .map(|(x, y)| image.get_pixel(x, y))
+        .collect();
+    assert_eq!(levels, [0, 255, 255, 0]);
+
+    raster.set_bits_per_pixel(2);
+    assert!(matches!(
+        raster.to_writer(&mut Vec::new(), ImageFormat::Bmp),
+        Err(Error::UnsupportedFormat(_))
+    ));
+}
@@ -0,0 +1,127 @@
+//! RGB-D input and the layer processor it is meant for.
+
+mod common;
+
+use std::fs;
+
+use holography::cgh::layer::CghProcessorLayer;
+use holography::cgh::CghProcessor;
+use holography::error::Error;
+use holography::lut::TriFuncNaive;
+use holography::object::Object3d;
+use holography::point::Point;
+use holography::raster::{ImageFormat, Pixels, Raster};
+use holography::rgbd::RgbdImage;
+
+use common::*;
+
+fn coordinates(object: &Object3d) -> Vec<[f64; 3]> {
+    object.points().iter().map(|p| [p.x, p.y, p.z]).collect()
+}
+
+/// Unlit pixels are skipped; the others are centered on the image, with the
+/// depth scaled to the range and the square root of the intensity as
+/// amplitude.
+#[test]
+fn lit_pixels_become_points() {
+    let image = Raster::new(2, 2, Pixels::Gray8(vec![0, 255, 64, 0]));
+    let depth = Raster::new(2, 2, Pixels::Gray16(vec![0, 65535, 13107, 0]));
+    let rgbd = RgbdImage::from_rasters(&image, &depth).unwrap();
+    assert_eq!((rgbd.width(), rgbd.height()), (2, 2));
+    assert_eq!(rgbd.depth()[(0, 1)], 1.0);
+    assert!(rgbd.colors().is_none());
+
+    let object = rgbd.to_object(100.0);
+    assert_eq!(
+        coordinates(&object),
+        [[0.0, -1.0, 100.0], [-1.0, 0.0, 20.0]]
+    );
+    let amplitudes = object.amplitudes();
+    assert_eq!(amplitudes[0], 1.0);
+    assert!((amplitudes[1] - (64.0f64 / 255.0).sqrt()).abs() < 1e-12);
+    assert!(object.colors().is_none());
+}
+
+#[test]
+fn rgb_images_keep_their_colors() {
+    let image = Raster::new(2, 1, Pixels::Rgb8(vec![255, 0, 0, 0, 0, 0]));
+    let depth = Raster::new(2, 1, Pixels::Gray8(vec![255, 0]));
+    let rgbd = RgbdImage::from_rasters(&image, &depth).unwrap();
+    assert!((rgbd.intensity()[(0, 0)] - 0.299).abs() < 1e-12);
+
+    let object = rgbd.to_object(10.0);
+    assert_eq!(coordinates(&object), [[-1.0, -0.5, 10.0]]);
+    assert_eq!(object.colors().unwrap(), &[[1.0, 0.0, 0.0]]);
+}
+
+#[test]
+fn mismatched_inputs_are_rejected() {
+    let image = Raster::new(2, 2, Pixels::Gray8(vec![1; 4]));
+    let small = Raster::new(2, 1, Pixels::Gray8(vec![1; 2]));
+    let rgb = Raster::new(2, 2, Pixels::Rgb8(vec![1; 12]));
+
+    for (image, depth) in [(&image, &small), (&image, &rgb)] {
+        assert!(matches!(
+            RgbdImage::from_rasters(image, depth),
+            Err(Error::InvalidImage(_))
+        ));
+    }
+}
+
+#[test]
+fn pairs_are_opened_by_extension() {
+    let directory = std::env::temp_dir().join(format!("holography-rgbd-{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    let image_path = directory.join("image.pgm");
+    let depth_path = directory.join("depth.bmp");
+    Raster::new(3, 1, Pixels::Gray8(vec![255, 0, 255]))
+        .save(&image_path, ImageFormat::Pnm)
+        .unwrap();
+    Raster::new(3, 1, Pixels::Gray8(vec![0, 0, 255]))
+        .save(&depth_path, ImageFormat::Bmp)
+        .unwrap();
+
+    let opened = RgbdImage::open(&image_path, &depth_path);
+    let unknown = RgbdImage::open(&image_path, directory.join("depth.raw"));
+    fs::remove_dir_all(&directory).unwrap();
+
+    let object = opened.unwrap().to_object(1.0);
+    assert_eq!(coordinates(&object), [[-1.5, -0.5, 0.0], [0.5, -0.5, 1.0]]);
+    match unknown {
+        Err(Error::File { source, .. }) => {
+            assert!(matches!(*source, Error::UnsupportedFormat(_)))
+        }
+        other => panic!("expected an unsupported format, got {:?}", other),
+    }
+}
+
+/// Random layer phases are reproducible for a seed and, for a single point,
+/// only change the phase of the whole field.
+#[test]
+fn random_layer_phases() {
+    let config = config();
+    let object = single_point();
+    let layer = |random_phase| CghProcessorLayer {
+        random_phase,
+        ..Default::default()
+    };
+
+    let plain = layer(None).field(&TriFuncNaive, &config, &object);
+    let random = layer(Some(5)).field(&TriFuncNaive, &config, &object);
+    let again = layer(Some(5)).field(&TriFuncNaive, &config, &object);
+
+    assert_eq!(random, again);
+    let ratio = random[(0, 0)] / plain[(0, 0)];
+    assert!((ratio.norm() - 1.0).abs() < 1e-9);
+    assert!(max_difference(&random, &(&plain * ratio)) < 1e-9);
+}
+
+#[test]
+fn points_outside_the_hologram_are_dropped() {
+    let config = config();
+    let outside = Object3d::new(vec![Point::new(WIDTH as f64, 0.0, 0.0)]);
+
+    let field = CghProcessorLayer::default().field(&TriFuncNaive, &config, &outside);
+
+    assert!(field.iter().all(|value| *value == 0.0.into()));
+}
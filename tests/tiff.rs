@@ -0,0 +1,106 @@
+//! TIFF output.
+
+use holography::error::Error;
+use holography::raster::{ImageFormat, Pixels, Raster};
+
+fn u16_at(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes(bytes[offset..offset + 2].try_into().unwrap())
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+/// The entries of the first IFD as `(tag, type, value)`.
+fn ifd_entries(bytes: &[u8]) -> Vec<(u16, u16, u32)> {
+    assert_eq!(&bytes[..4], b"II\x2a\x00");
+    let ifd = u32_at(bytes, 4) as usize;
+    assert_eq!(ifd % 2, 0, "IFD on a word boundary");
+
+    let count = u16_at(bytes, ifd) as usize;
+    let entries = (0..count)
+        .map(|i| {
+            let entry = ifd + 2 + i * 12;
+            assert_eq!(u32_at(bytes, entry + 4), 1, "one value per entry");
+            let kind = u16_at(bytes, entry + 2);
+            let value = match kind {
+                3 => u16_at(bytes, entry + 8) as u32,
+                _ => u32_at(bytes, entry + 8),
+            };
+            (u16_at(bytes, entry), kind, value)
+        })
+        .collect();
+    assert_eq!(u32_at(bytes, ifd + 2 + count * 12), 0, "a single IFD");
+
+    entries
+}
+
+fn value(entries: &[(u16, u16, u32)], tag: u16) -> u32 {
+    entries
+        .iter()
+        .find(|entry| entry.0 == tag)
+        .unwrap_or_else(|| panic!("no tag {}", tag))
+        .2
+}
+
+#[test]
+fn float_phase_map_layout() {
+    let phase = [-3.0f32, 0.5, 1.25, 3.0, 0.0, -0.125];
+    let mut raster = Raster::new(3, 2, Pixels::GrayF32(phase.to_vec()));
+    raster.set_pixel_pitch(8.0e-6);
+    let mut bytes = Vec::new();
+    raster.to_writer(&mut bytes, ImageFormat::Tiff).unwrap();
+
+    let entries = ifd_entries(&bytes);
+    let tags: Vec<_> = entries.iter().map(|entry| entry.0).collect();
+    assert!(tags.windows(2).all(|pair| pair[0] < pair[1]), "sorted tags");
+    assert_eq!(value(&entries, 256), 3);
+    assert_eq!(value(&entries, 257), 2);
+    assert_eq!(value(&entries, 258), 32);
+    assert_eq!(value(&entries, 259), 1);
+    assert_eq!(value(&entries, 339), 3, "float samples");
+
+    let offset = value(&entries, 273) as usize;
+    assert_eq!(value(&entries, 279), 24);
+    let samples: Vec<_> = bytes[offset..offset + 24]
+        .chunks_exact(4)
+        .map(|sample| f32::from_le_bytes(sample.try_into().unwrap()))
+        .collect();
+    assert_eq!(samples, phase);
+
+    // 1250 pixels per centimeter.
+    assert_eq!(value(&entries, 296), 3);
+    let resolution = value(&entries, 282) as usize;
+    assert_eq!(
+        (u32_at(&bytes, resolution), u32_at(&bytes, resolution + 4)),
+        (1250, 1)
+    );
+}
+
+#[test]
+fn integer_samples_and_padding() {
+    let raster = Raster::new(3, 1, Pixels::Gray8(vec![1, 2, 3]));
+    let mut bytes = Vec::new();
+    raster.to_writer(&mut bytes, ImageFormat::Tiff).unwrap();
+
+    let entries = ifd_entries(&bytes);
+    assert_eq!(value(&entries, 258), 8);
+    assert_eq!(value(&entries, 339), 1, "unsigned samples");
+    assert_eq!(value(&entries, 296), 1, "no absolute unit");
+    assert_eq!(&bytes[8..11], [1, 2, 3]);
+
+    let raster = Raster::new(1, 1, Pixels::Gray16(vec![0x0102]));
+    let mut bytes = Vec::new();
+    raster.to_writer(&mut bytes, ImageFormat::Tiff).unwrap();
+    assert_eq!(value(&ifd_entries(&bytes), 258), 16);
+    assert_eq!(&bytes[8..10], [2, 1]);
+}
+
+#[test]
+fn rgb_is_unsupported() {
+    let raster = Raster::new(1, 1, Pixels::Rgb8(vec![1, 2, 3]));
+    assert!(matches!(
+        raster.to_writer(&mut Vec::new(), ImageFormat::Tiff),
+        Err(Error::UnsupportedFormat(_))
+    ));
+}